@@ -2,10 +2,89 @@
 use ggez::event::{self};
 use ggez::ContextBuilder;
 mod mygame;
-use mygame::MyGame;
+mod tools;
+use mygame::{predict, random, MyGame};
 const WIDTH: f32 = 480.0;
-const HEIGHT: f32 = 540.0;
+const HEIGHT: f32 = 724.0;
+/*
+    起動時の引数
+        (なし)                      : 組み込みのCPUと対戦
+        --seed <シード> ..            : 最初の対局の乱数のシードを指定(以降の引数は通常通り)
+        --engine <コマンド> [引数..] : 外部エンジンをCPUとして対戦
+        --engine-mode [設定]         : 自身を外部エンジンとして動かす(設定はEngineConfig::parseの形式)
+        match <エンジンA> <エンジンB> ..: エンジン同士の対戦(tools/matchup.rs)
+        perft <深さ> [盤面 手番]      : 合法手生成の検証(tools/perft.rs)
+        eval <盤面> <手番> [重み]     : 評価関数の特徴量の表示(tools/eval.rs)
+        selfplay <対局数> <ファイル>  : 学習用の棋譜の作成(tools/train.rs)
+        train <棋譜ファイル..>        : パターン評価の重みの学習(tools/train.rs)
+        book <棋譜ファイル..>         : 定石ファイルの作成(tools/book.rs)
+        replay <シード 棋譜> [設定]   : 対局の記録からCPUの手を再現(tools/replay.rs)
+*/
 fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut seed = random::new_seed();
+    if args.get(0).map(|s| s.as_str()) == Some("--seed") && args.len() >= 2 {
+        match args[1].parse() {
+            Ok(n) => seed = n,
+            Err(_) => {
+                eprintln!("シードが不正です: {}", args[1]);
+                return;
+            }
+        }
+        args.drain(..2);
+    }
+    let cpu = match args.get(0).map(|s| s.as_str()) {
+        Some("--engine-mode") => {
+            match predict::EngineConfig::parse(args.get(1).map(|s| s.as_str()).unwrap_or("")) {
+                Ok(config) => predict::external::run_engine_mode(config),
+                Err(e) => eprintln!("{}", e),
+            }
+            return;
+        }
+        Some("match") => {
+            tools::matchup::run(&args[1..]);
+            return;
+        }
+        Some("perft") => {
+            tools::perft::run(&args[1..]);
+            return;
+        }
+        Some("eval") => {
+            tools::eval::run(&args[1..]);
+            return;
+        }
+        Some("selfplay") => {
+            tools::train::run_selfplay(&args[1..]);
+            return;
+        }
+        Some("train") => {
+            tools::train::run_train(&args[1..]);
+            return;
+        }
+        Some("book") => {
+            tools::book::run(&args[1..]);
+            return;
+        }
+        Some("replay") => {
+            tools::replay::run(&args[1..]);
+            return;
+        }
+        Some("--engine") if args.len() >= 2 => {
+            match predict::external::ExternalEngine::spawn(&args[1], &args[2..]) {
+                Ok(engine) => {
+                    predict::Engine::External(predict::external::ExternalPlayer::new(engine))
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    predict::Engine::MonteCarlo(predict::EngineConfig::default())
+                }
+            }
+        }
+        _ => predict::Engine::MonteCarlo(predict::EngineConfig::default()),
+    };
+    //学習済みのパターン評価の重みと定石を読み込んでおく(なければそれぞれなしで思考する)
+    predict::pattern::shared();
+    predict::book::shared();
     let resource_dir = std::path::PathBuf::from("./resources");
 
     let (mut ctx, event_loop) = ContextBuilder::new("my_game", "Ide")
@@ -27,6 +106,6 @@ fn main() {
         .expect("Could not create ggez context!");
     let title = "MyOthelloGame".to_string();
     ggez::graphics::set_window_title(&ctx, &title);
-    let my_game = MyGame::new(&mut ctx, WIDTH as u32, HEIGHT as u32);
+    let my_game = MyGame::new(&mut ctx, WIDTH as u32, HEIGHT as u32, cpu, seed);
     event::run(ctx, event_loop, my_game);
 }
@@ -4,14 +4,30 @@ use ggez::{graphics, Context, GameResult};
 use glam::*;
 use stone::Stone;
 use user::User;
-mod board;
+mod analysis;
+pub mod board;
 mod button;
+mod coach;
 mod detailedstatus;
+pub mod difficulty;
+mod editor;
+mod evalgraph;
+mod explain;
+pub mod gametree;
+mod hint;
+mod illegal;
 mod mouse;
 mod particles;
-mod predict;
+pub mod predict;
+mod puzzle;
+pub mod random;
+pub mod record;
+mod replayviewer;
+mod review;
 mod se;
-mod stone;
+mod settings;
+pub mod stone;
+mod tutorial;
 mod user;
 pub struct MyGame {
     pub board: board::Board,
@@ -26,23 +42,89 @@ pub struct MyGame {
     font: graphics::Font,
     se: se::Se,
     status: detailedstatus::Status,
+    cpu: predict::Engine,
+    seed: u64,                //この対局の乱数のシード
+    tree: gametree::GameTree, //この対局の変化を含む棋譜
+    recorded: bool,           //棋譜を最後に変えてから記録したか
+    settings: settings::Settings,
+    analysis: analysis::Analysis,
+    hint: hint::Hint,
+    graph: evalgraph::EvalGraph,
+    replay: Option<replayviewer::ReplayViewer>, //保存した対局を再生中
+    saved: Option<SavedGame>,                   //再生中に退避している対局
+    editor: Option<editor::Editor>,             //盤面を編集中
+    puzzle: Option<puzzle::PuzzleMode>,         //詰めオセロを出題中
+    tutorial: Option<tutorial::Tutorial>,       //ルールのチュートリアル中
+    illegal: Option<illegal::IllegalClick>,     //直前に置けないマスをクリックした
+    coach: coach::Coach,
+    explanation: Option<explain::Explanation>, //CPUの直前の手の説明
 }
+/*
+    再生などで盤面を一時的に使う間，退避しておく対局の状態
+*/
+struct SavedGame {
+    board: Board,
+    now: Stone,
+    passed: bool,
+    tree: gametree::GameTree,
+    recorded: bool,
+    game_end: bool,
+}
+#[derive(Clone, Copy, PartialEq)]
 pub enum ButtonEventList {
     Reset,
     Pass,
+    Difficulty,
+    Analysis,
+    Hint,
+    StepBack,
+    StepForward,
+    Graph,
+    Review,
+    Replay,
+    Autoplay,
+    MoveBack,
+    MoveForward,
+    VariationPrev,
+    VariationNext,
+    Promote,
+    DeleteVariation,
+    Load,
+    Edit,
+    EditTurn,
+    EditClear,
+    EditInitial,
+    Puzzle,
+    Tutorial,
+    Coach,
+    Explain,
 }
 const FONT_SIZE: f32 = 48.0;
 const SMALL_FONT_SIZE: f32 = 32.0;
 const USER_COLOR: Stone = Stone::White;
-const CPU_COLOR: Stone = Stone::Black;
+pub const CPU_COLOR: Stone = Stone::Black;
 pub const BOARDSIZE: usize = 8;
+const PARTICLE_STREAM: u64 = std::u64::MAX; //パーティクル用の乱数列(手数と重ならない番号)
+const HINT_STREAM: u64 = std::u64::MAX - 1; //ヒント用の乱数列
+const GRAPH_STREAM: u64 = std::u64::MAX - 2; //評価値のグラフ用の乱数列
+const COACH_STREAM: u64 = std::u64::MAX - 3; //コーチモード用の乱数列
+const STATS_TOP: f32 = button::TOOLBAR_BOTTOM + 2.0; //CPUの思考の統計を表示する位置(ツールバーの下)
+const STATS_FONT_SIZE: f32 = 14.0;
+const STATS_PV_LENGTH: usize = 4; //表示する読み筋の手数
+const BANNER_FONT_SIZE: f32 = 16.0; //盤面の上側の帯の文字の大きさ
 impl MyGame {
-    pub fn new(ctx: &mut Context, width: u32, height: u32) -> MyGame {
+    pub fn new(
+        ctx: &mut Context,
+        width: u32,
+        height: u32,
+        cpu: predict::Engine,
+        seed: u64,
+    ) -> MyGame {
         let fontpass = "/NotoSansJP-Regular.otf";
-        MyGame {
+        let mut game = MyGame {
             board: board::Board::new(BOARDSIZE, width, height),
             ui: button::UIs::new(),
-            particles: particles::Particles::new(),
+            particles: particles::Particles::new(random::derive(seed, PARTICLE_STREAM)),
             rotationrecord: [[0.0; BOARDSIZE]; BOARDSIZE],
             window_width: width,
             window_height: height,
@@ -55,7 +137,43 @@ impl MyGame {
             font: graphics::Font::new(ctx, fontpass).unwrap(),
             se: se::Se::new(ctx),
             status: detailedstatus::Status::new(0.0),
+            cpu: cpu,
+            seed: seed,
+            tree: gametree::GameTree::new(),
+            recorded: false,
+            settings: settings::Settings::load(),
+            analysis: analysis::Analysis::new(),
+            hint: hint::Hint::new(),
+            graph: evalgraph::EvalGraph::new(),
+            replay: None,
+            saved: None,
+            editor: None,
+            puzzle: None,
+            tutorial: None,
+            illegal: None,
+            coach: coach::Coach::new(false),
+            explanation: None,
+        };
+        game.coach.enabled = game.settings.coach;
+        game.apply_difficulty();
+        game.ui.set_text(ButtonEventList::Analysis, "Analyze: off");
+        game.set_coach_text();
+        game.set_explain_text();
+        game
+    }
+    /*
+        設定の難易度を組み込みのCPUへ反映し，ボタンの表示を更新する
+        外部エンジンの強さは変えられないので，組み込みのCPUのときのみ反映する
+    */
+    fn apply_difficulty(&mut self) {
+        let difficulty = self.settings.difficulty;
+        if let predict::Engine::MonteCarlo(config) = &mut self.cpu {
+            *config = difficulty.config();
         }
+        self.ui.set_text(
+            ButtonEventList::Difficulty,
+            &format!("Lv: {}", difficulty.name()),
+        );
     }
     /*
         UIの表示
@@ -131,36 +249,81 @@ impl MyGame {
             );
         }
         {
-            //CPUが探索を行っている時，その旨を描画
+            //CPUが探索を行っている時，その旨を盤面の上側に描画(ツールバーに重ならないように)
             if self.status.thinking {
-                let text = "CPU思考中・・・".to_string();
                 let transparency = if self.status.get_blink_description() < 0.5 {
                     0.5 + self.status.get_blink_description()
                 } else {
                     1.5 - self.status.get_blink_description()
                 };
-                let text = graphics::Text::new(graphics::TextFragment {
-                    text: text,
-                    color: Some(graphics::Color::new(0.9, 0.9, 0.9, transparency)),
-                    font: Some(self.font),
-                    scale: Some(graphics::PxScale::from(FONT_SIZE)),
-                });
-                graphics::draw(
+                draw_banner(
                     ctx,
-                    &text,
-                    (Vec2::new(
-                        (self.window_width as f32) / 2.0 - text.dimensions(ctx).w / 2.0,
-                        (self.window_height as f32) / 1.3 - text.dimensions(ctx).h / 2.0,
-                    ),),
+                    &self.font,
+                    "CPU思考中・・・",
+                    graphics::Color::new(0.9, 0.9, 0.9, transparency),
                 )?;
             }
         }
+        {
+            //CPUの前回の思考の統計を描画(思考中は盤面に途中経過を表示するので描かない)
+            if let (false, Some(stats)) = (self.status.thinking, self.cpu.last_stats()) {
+                if self.tree.depth() != 0 {
+                    let mut text = format!(
+                        "{} {:.1}秒 {:.0}局面/秒 深さ{}",
+                        stats.method.name(),
+                        stats.elapsed.as_secs_f32(),
+                        stats.nps(),
+                        stats.max_depth
+                    );
+                    if stats.pv.len() != 0 {
+                        let pv = stats.pv.iter().take(STATS_PV_LENGTH);
+                        let pv: Vec<String> = pv.map(|&pos| Board::pos_to_notation(pos)).collect();
+                        text += &format!(" {}", pv.join(" "));
+                    }
+                    let text = graphics::Text::new((text, self.font, STATS_FONT_SIZE));
+                    graphics::draw(
+                        ctx,
+                        &text,
+                        (Vec2::new(
+                            (self.window_width as f32) / 2.0 - text.dimensions(ctx).w / 2.0,
+                            STATS_TOP,
+                        ),),
+                    )?;
+                }
+            }
+        }
         //UI類の表示
         for it in self.ui.buttons.iter() {
             it.draw(ctx, &self.font, &self.mouse_inf, &self.board)?;
         }
         Ok(())
     }
+    /*
+        CPUの思考中，探索の途中経過を盤面に描画する
+        各候補手へ試行回数の割合を表示し，現時点で最も有力な手を囲む
+    */
+    fn draw_search_progress(&self, ctx: &mut Context) -> GameResult<()> {
+        if !self.status.thinking {
+            return Ok(());
+        }
+        let progress = match self.cpu.progress() {
+            Some(progress) => progress,
+            None => return Ok(()),
+        };
+        let total = progress.moves.iter().map(|m| m.visits).sum::<u32>().max(1);
+        self.board.draw_move_stats(
+            ctx,
+            &self.font,
+            &progress.moves,
+            &|m| format!("{:.0}%", m.visits as f32 * 100.0 / total as f32),
+            graphics::Color::new(0.2, 0.9, 1.0, 1.0),
+            &format!(
+                "CPU {:.1}秒 {}回",
+                progress.start.elapsed().as_secs_f32(),
+                progress.iterations
+            ),
+        )
+    }
     fn draw_can_rotate(&self, ctx: &mut Context) -> GameResult<()> {
         //現在ユーザー側のターンかつ置ける位置にマウスオーバーしている場合，ひっくり返る石を表示する
         if self.ret_nowuser() as i32 == Stone::White as i32 {
@@ -189,6 +352,51 @@ impl MyGame {
         }
         Ok(())
     }
+    //ヒントの位置を，ひっくり返る石の表示と同じ広がる円で示す
+    fn draw_hint(&self, ctx: &mut Context) -> GameResult<()> {
+        if let Some(pos) = self.hint.pos() {
+            let cell_size: f32 =
+                std::cmp::min(self.window_width, self.window_height) as f32 / BOARDSIZE as f32;
+            self.board.draw_placed_rotation_stone(
+                ctx,
+                &vec![pos],
+                self.status.get_placed_rotating_stone_count(),
+                cell_size,
+            )?;
+        }
+        Ok(())
+    }
+    /*
+        棋譜の木で現在の局面から打たれた手を，変化の番号を付けた円で示す(本線は1で白い円)
+    */
+    fn draw_next_moves(&self, ctx: &mut Context) -> GameResult<()> {
+        let cell_size = self.window_width as f32 / BOARDSIZE as f32;
+        for (i, pos) in self.tree.next_moves().into_iter().enumerate() {
+            let color = if i == 0 {
+                graphics::Color::WHITE
+            } else {
+                graphics::Color::new(0.6, 0.6, 0.6, 1.0)
+            };
+            let center = Vec2::new(
+                pos.1 as f32 * cell_size + cell_size / 2.0,
+                pos.0 as f32 * cell_size + cell_size / 2.0,
+            );
+            let ring = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::stroke(3.0),
+                Vec2::new(0.0, 0.0),
+                cell_size / 2.5,
+                0.1,
+                color,
+            )?;
+            graphics::draw(ctx, &ring, (center,))?;
+            let text =
+                graphics::Text::new((format!("{}", i + 1), self.font, STATS_FONT_SIZE * 1.5));
+            let (w, h) = (text.width(ctx), text.height(ctx));
+            graphics::draw(ctx, &text, (center - Vec2::new(w / 2.0, h / 2.0), color))?;
+        }
+        Ok(())
+    }
     /*
         ゲーム終了時にどちらが勝利したかを表示
     */
@@ -248,8 +456,290 @@ impl MyGame {
     pub fn ret_nowuser(&self) -> Stone {
         return self.now_user.now;
     }
+    /*
+        再生・詰めオセロで盤面を使う間，対局の状態を退避する
+        対局の局面の解析・ヒント・グラフの表示もやめる
+    */
+    fn stash_game(&mut self) {
+        self.saved = Some(SavedGame {
+            board: self.board,
+            now: self.now_user.now,
+            passed: self.passed,
+            tree: self.tree.clone(),
+            recorded: self.recorded,
+            game_end: self.status.game_end,
+        });
+        self.analysis.stop();
+        self.hint.clear();
+        self.graph.visible = false;
+        self.graph.view = None;
+    }
+    //退避していた対局に戻る
+    fn restore_game(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            self.board = saved.board;
+            self.now_user.now = saved.now;
+            self.passed = saved.passed;
+            self.tree = saved.tree;
+            self.recorded = saved.recorded;
+            self.status.game_end = saved.game_end;
+        }
+        self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+    }
+    /*
+        対局の状態を退避し，viewerの記録の再生を始める
+    */
+    fn enter_replay(&mut self, viewer: replayviewer::ReplayViewer) {
+        let (board, color) = match viewer.position(0, self.window_width, self.window_height) {
+            Some(v) => v,
+            None => return,
+        };
+        self.stash_game();
+        self.board = board;
+        self.now_user.now = color;
+        self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+        self.replay = Some(viewer);
+        self.ui.set_text(ButtonEventList::Replay, "Exit");
+        self.ui.set_text(ButtonEventList::StepBack, "<");
+        self.ui.set_text(ButtonEventList::StepForward, ">");
+    }
+    //再生をやめ，退避していた対局に戻る
+    fn exit_replay(&mut self) {
+        if self.replay.take().is_none() {
+            return;
+        }
+        self.restore_game();
+        self.ui.set_text(ButtonEventList::Replay, "Replay");
+        self.ui.set_text(ButtonEventList::Autoplay, "Auto: off");
+        self.ui.set_text(ButtonEventList::StepBack, "PV <");
+        self.ui.set_text(ButtonEventList::StepForward, "PV >");
+    }
+    /*
+        再生中の記録の次の手を，対局中と同じ石の回転・パーティクル・効果音で打つ
+    */
+    fn replay_next(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let (color, pos) = match self.replay.as_mut().and_then(|replay| replay.next_move()) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        self.play_with_effects(ctx, color, pos)
+    }
+    /*
+        対局の棋譜に残さずに，colorでposに対局中と同じ石の回転・パーティクル・効果音で打ち，手番を渡す
+        (再生・詰めオセロ・チュートリアル用)
+    */
+    fn play_with_effects(
+        &mut self,
+        ctx: &mut Context,
+        color: Stone,
+        pos: (usize, usize),
+    ) -> GameResult<()> {
+        let list = predict::can_cnt(&self.board.gamebord, color, pos, false);
+        self.board.setstone(&list, color);
+        self.board.count_stone();
+        self.set_rotation_stone(&list);
+        self.particles.create_stone_particle(
+            self.board.boardcoordinate_to_screencoordinate(pos),
+            25,
+            &color,
+        );
+        self.se.play_stone(ctx)?;
+        self.now_user.now = color.return_reverse_color();
+        Ok(())
+    }
+    //再生中の記録を一手戻す(戻す時は演出なしで盤面を置き換える)
+    fn replay_back(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let mut position = None;
+        if let Some(replay) = &mut self.replay {
+            if replay.back() {
+                position = replay.position(replay.index, self.window_width, self.window_height);
+            }
+        }
+        if let Some((board, color)) = position {
+            self.se.play_button(ctx)?;
+            self.board = board;
+            self.now_user.now = color;
+            self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+        }
+        Ok(())
+    }
+    //再生中・盤面編集中・詰めオセロの出題中・チュートリアル中でなく，対局中の盤面を表示しているか
+    fn is_playing(&self) -> bool {
+        self.replay.is_none()
+            && self.editor.is_none()
+            && self.puzzle.is_none()
+            && self.tutorial.is_none()
+    }
+    //対局の状態を退避し，詰めオセロを始める
+    fn enter_puzzle(&mut self, mode: puzzle::PuzzleMode) {
+        self.stash_game();
+        self.puzzle = Some(mode);
+        self.show_puzzle();
+        self.ui.set_text(ButtonEventList::Puzzle, "Exit");
+        self.ui.set_text(ButtonEventList::StepBack, "<");
+        self.ui.set_text(ButtonEventList::StepForward, ">");
+    }
+    //詰めオセロをやめ，退避していた対局に戻る
+    fn exit_puzzle(&mut self) {
+        if self.puzzle.take().is_none() {
+            return;
+        }
+        self.restore_game();
+        self.ui.set_text(ButtonEventList::Puzzle, "Puzzle");
+        self.ui.set_text(ButtonEventList::StepBack, "PV <");
+        self.ui.set_text(ButtonEventList::StepForward, "PV >");
+    }
+    //出題中の問題の局面を盤面に表示する
+    fn show_puzzle(&mut self) {
+        let start = match &self.puzzle {
+            Some(puzzle) => puzzle.current().start,
+            None => return,
+        };
+        if let Some((board, color)) =
+            record::replay_from(Some(start), &[], self.window_width, self.window_height)
+        {
+            self.board = board;
+            self.now_user.now = color;
+        }
+        self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+    }
+    /*
+        詰めオセロでクリックしたマスを解答として判定し，対局中と同じ演出で石を置く
+        解答済みの場合はもう一度同じ問題を出す
+    */
+    fn puzzle_click(&mut self, ctx: &mut Context, pos: (usize, usize)) -> GameResult<()> {
+        let puzzle = match &mut self.puzzle {
+            Some(puzzle) => puzzle,
+            None => return Ok(()),
+        };
+        if puzzle.answer.is_some() {
+            puzzle.select(puzzle.index as i32);
+            self.show_puzzle();
+            return Ok(());
+        }
+        let correct = match puzzle.answer(pos) {
+            Some(answer) => answer.correct(),
+            None => return Ok(()),
+        };
+        self.play_with_effects(ctx, self.now_user.now, pos)?;
+        if correct {
+            self.particles.create_confetti();
+        }
+        Ok(())
+    }
+    fn set_coach_text(&mut self) {
+        let text = if self.coach.enabled {
+            "Coach: on"
+        } else {
+            "Coach: off"
+        };
+        self.ui.set_text(ButtonEventList::Coach, text);
+    }
+    fn set_explain_text(&mut self) {
+        let text = if self.settings.explain {
+            "Explain: on"
+        } else {
+            "Explain: off"
+        };
+        self.ui.set_text(ButtonEventList::Explain, text);
+    }
+    //対局の状態を退避し，チュートリアルを始める
+    fn enter_tutorial(&mut self) {
+        self.stash_game();
+        self.tutorial = Some(tutorial::Tutorial::new());
+        self.show_tutorial();
+        self.ui.set_text(ButtonEventList::Tutorial, "Exit");
+        self.ui.set_text(ButtonEventList::StepBack, "<");
+        self.ui.set_text(ButtonEventList::StepForward, ">");
+    }
+    //チュートリアルをやめ，退避していた対局に戻る
+    fn exit_tutorial(&mut self) {
+        if self.tutorial.take().is_none() {
+            return;
+        }
+        self.restore_game();
+        self.ui.set_text(ButtonEventList::Tutorial, "Tutorial");
+        self.ui.set_text(ButtonEventList::StepBack, "PV <");
+        self.ui.set_text(ButtonEventList::StepForward, "PV >");
+    }
+    //チュートリアルの現在の段階の局面を盤面に表示する
+    fn show_tutorial(&mut self) {
+        let position = match &self.tutorial {
+            Some(tutorial) => tutorial.position(self.window_width, self.window_height),
+            None => return,
+        };
+        if let Some((board, color)) = position {
+            self.board = board;
+            self.now_user.now = color;
+        }
+        self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+    }
+    /*
+        盤面編集を始める．今の盤面と手番から編集する
+    */
+    fn enter_editor(&mut self) {
+        self.analysis.stop();
+        self.hint.clear();
+        self.graph.visible = false;
+        self.graph.view = None;
+        self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+        self.editor = Some(editor::Editor::new());
+        self.ui.set_text(ButtonEventList::Edit, "Done");
+        self.set_editor_buttons_visible(true);
+        self.set_turn_text();
+    }
+    /*
+        盤面編集を終え，編集した局面から新しい対局を始める
+        続けられない局面の場合は編集を続ける
+    */
+    fn exit_editor(&mut self) -> bool {
+        let start = match &mut self.editor {
+            Some(editor) => match editor.finish(&self.board, self.now_user.now) {
+                Some(start) => start,
+                None => return false,
+            },
+            None => return false,
+        };
+        self.close_editor();
+        self.reset_game();
+        self.tree.start = Some(start);
+        self.show_tree_position();
+        true
+    }
+    //盤面編集の表示をやめる(盤面はそのまま)
+    fn close_editor(&mut self) {
+        self.editor = None;
+        self.ui.set_text(ButtonEventList::Edit, "Edit");
+        self.ui.set_text(ButtonEventList::EditTurn, "Turn");
+        self.set_editor_buttons_visible(false);
+    }
+    //盤面編集のボタン(手番・全消去・初期配置)を表示するか
+    fn set_editor_buttons_visible(&mut self, visible: bool) {
+        self.ui.set_visible(ButtonEventList::EditTurn, visible);
+        self.ui.set_visible(ButtonEventList::EditClear, visible);
+        self.ui.set_visible(ButtonEventList::EditInitial, visible);
+    }
+    //盤面編集中の手番をボタンに表示する
+    fn set_turn_text(&mut self) {
+        let text = if self.now_user.now as i32 == Stone::White as i32 {
+            "Turn: White"
+        } else {
+            "Turn: Black"
+        };
+        self.ui.set_text(ButtonEventList::EditTurn, text);
+    }
     //ゲーム内容を初期化
     fn reset_game(&mut self) {
+        //途中で終えた対局も，CPUの手を再現できるよう記録しておく
+        if !self.recorded && !self.tree.is_empty() {
+            self.save_record();
+        }
+        self.seed = random::new_seed();
+        self.tree = gametree::GameTree::new();
+        self.recorded = false;
+        self.hint = hint::Hint::new();
+        self.graph.stop();
+        self.graph = evalgraph::EvalGraph::new();
         self.board = board::Board::new(BOARDSIZE, self.window_width, self.window_height);
         self.now_user = User { now: Stone::White };
         self.passed = false;
@@ -258,9 +748,77 @@ impl MyGame {
             pressed: false,
         };
         self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
-        self.particles = particles::Particles::new();
+        self.particles = particles::Particles::new(random::derive(self.seed, PARTICLE_STREAM));
+        self.illegal = None;
+        self.explanation = None;
         self.status.game_end = false;
     }
+    //シードと棋譜を記録する
+    fn save_record(&mut self) {
+        let level = match self.cpu {
+            predict::Engine::MonteCarlo(_) => {
+                Some(format!("level={}", self.settings.difficulty.name()))
+            }
+            predict::Engine::External(_) => None,
+        };
+        let record = record::GameRecord {
+            seed: self.seed,
+            tree: self.tree.clone(),
+            config: level,
+            hints: self.hint.count,
+        };
+        if let Err(e) = record.save() {
+            eprintln!("{}へ記録できません: {}", record::GAME_RECORD_FILE, e);
+        }
+        self.recorded = true;
+    }
+    //棋譜の木に手を加える(新しい変化になったら記録し直す)
+    fn push_move(&mut self, pos: (usize, usize)) {
+        if self.tree.play(pos) {
+            self.recorded = false;
+        }
+    }
+    /*
+        棋譜の木で移った局面を盤面に反映する
+        局面が変わるので，ヒント・評価値のグラフ・終局の判定は作り直す
+    */
+    fn show_tree_position(&mut self) {
+        let (board, color) =
+            match self
+                .tree
+                .replay(&self.tree.moves(), self.window_width, self.window_height)
+            {
+                Some(v) => v,
+                None => return,
+            };
+        self.board = board;
+        self.now_user.now = color;
+        self.rotationrecord = [[0.0; BOARDSIZE]; BOARDSIZE];
+        self.hint.clear();
+        self.graph.stop();
+        self.graph = evalgraph::EvalGraph::new();
+        self.illegal = None;
+        self.explanation = None;
+        self.status.game_end = false;
+    }
+    /*
+        記録の最も新しい対局を，変化を含めて読み込み本線の最後の局面を表示する
+        今の対局は記録していなければ記録しておく
+    */
+    fn load_game(&mut self) -> Result<(), String> {
+        let record = record::GameRecord::load_latest()?;
+        if !self.recorded && !self.tree.is_empty() {
+            self.save_record();
+        }
+        self.seed = record.seed;
+        self.tree = record.tree;
+        self.tree.forward_to_end();
+        self.recorded = true;
+        self.hint = hint::Hint::new();
+        self.hint.count = record.hints;
+        self.show_tree_position();
+        Ok(())
+    }
     //引数で与えられた石達を回転中or回転待機と設定する
     fn set_rotation_stone(&mut self, list: &Vec<(usize, usize)>) {
         let delay = 0.5; //遠くにある石はdelayの値だけひっくり返るのを遅らせる
@@ -304,6 +862,24 @@ impl MyGame {
         }
     }
     //ゲームが終了しているか否かを返す
+    /*
+        対局が終わった最初のフレームで，勝った場合の紙吹雪・棋譜の記録・評価値のグラフの計算を始める
+        再生中は記録の終局で対局を終えた扱いにしない
+    */
+    fn update_game_end(&mut self) {
+        if !self.is_playing() || !self.is_gameend() || self.status.game_end {
+            return;
+        }
+        if self.board.return_win() as i32 == USER_COLOR as i32 {
+            self.particles.create_confetti();
+        }
+        if !self.recorded {
+            self.save_record();
+        }
+        let seed = random::derive(self.seed, GRAPH_STREAM);
+        self.graph.start(self.tree.start, &self.tree.moves(), seed);
+        self.status.game_end = true;
+    }
     pub fn is_gameend(&self) -> bool {
         predict::can_set_pos(&self.board, Stone::Black).len() == 0
             && predict::can_set_pos(&self.board, Stone::White).len() == 0
@@ -313,7 +889,14 @@ impl MyGame {
         //None:現在のターン側の色ではない
         //Some(Ok):現在のターン　これが終わったらupdate関数はreturnを行う
         if self.ret_nowuser() as i32 == stone as i32 {
-            let pos = predict::montecarlo::montecarlotree(&self.board, stone, &self.status);
+            //一手ごとの乱数は対局のシードと手数から作る
+            let seed = random::derive(self.seed, self.tree.depth() as u64);
+            //別の局面を探索した結果は，エンジン側で捨てて探索し直す
+            let pos = self.cpu.think(&self.board, stone, &self.status, seed);
+            if pos.is_none() {
+                //石の回転中に探索を始めた場合も，その間に局面を動かせないよう思考中とする
+                self.status.thinking = true;
+            }
             if self.status.rotating {
                 return Some(Ok(())); //石の回転中は停止
             }
@@ -323,8 +906,15 @@ impl MyGame {
                         None => {} //置ける場所無し
                         Some(w) => {
                             //wに置くという計算結果
+                            self.explanation = explain::Explanation::new(
+                                &self.board,
+                                stone,
+                                w,
+                                self.cpu.last_stats().as_ref(),
+                            );
                             let list = predict::can_cnt(&self.board.gamebord, stone, w, false);
                             self.board.setstone(&list, stone);
+                            self.push_move(w);
                             self.set_rotation_stone(&list);
                             self.particles.create_stone_particle(
                                 self.board.boardcoordinate_to_screencoordinate(w),
@@ -370,8 +960,22 @@ impl MyGame {
                         println!("{:?}", i);
                     }
                     println!("");
-                    if list.len() > 0 {
+                    let pos = (t.0 as usize, t.1 as usize);
+                    if let Some(reason) = illegal::Reason::of(&self.board, stone, pos) {
+                        //置けない理由を表示し，マスを赤く光らせる
+                        self.illegal = Some(illegal::IllegalClick::new(pos, reason));
+                        if self.settings.illegal_sound {
+                            self.se.play_illegal(ctx).unwrap();
+                        }
+                    } else if !self.coach.confirm(&self.board, stone, pos) {
+                        //警告のある手は，もう一度クリックされるまで打たない
+                        self.illegal = None;
+                        self.se.play_button(ctx).unwrap();
+                    } else {
+                        self.illegal = None;
+                        self.explanation = None;
                         let _ = self.board.setstone(&list, self.ret_nowuser());
+                        self.push_move((t.0 as usize, t.1 as usize));
                         self.now_user.nextuser();
                         self.set_rotation_stone(&list);
                         self.particles.create_stone_particle(
@@ -397,10 +1001,27 @@ impl EventHandler for MyGame {
         //文字の点滅と，ひっくり返る石候補の提示に使う値の更新
         self.status
             .update_status(ggez::timer::delta(ctx).as_secs_f32() * 1.0)?;
+        //置けないマスをクリックしたときの表示の時間を進める
+        let delta = ggez::timer::delta(ctx).as_secs_f32();
+        if let Some(illegal) = &mut self.illegal {
+            if !illegal.update(delta) {
+                self.illegal = None;
+            }
+        }
         //パーティクルステータスの更新
         self.particles
             .update(ggez::timer::delta(ctx).as_secs_f32())?;
-        let title = "MyOthelloGame".to_string();
+        self.update_game_end();
+        let (variation, variations) = self.tree.variation();
+        let mut title = format!(
+            "MyOthelloGame (seed {} {}手目",
+            self.seed,
+            self.tree.depth()
+        );
+        if !self.tree.is_main_line() || variations > 1 {
+            title += &format!(" 変化{}/{}", variation + 1, variations);
+        }
+        title += ")";
         graphics::set_window_title(ctx, &title);
         //回転中の石ステータスの更新
         self.update_rotation_stone(ctx);
@@ -417,30 +1038,359 @@ impl EventHandler for MyGame {
         for event in buttoneventlist {
             match event {
                 ButtonEventList::Reset => {
-                    self.se.play_button(ctx)?;
-                    self.reset_game();
+                    //思考中にリセットすると，前の対局の局面の手が新しい対局に打たれてしまう
+                    if !self.status.thinking {
+                        self.se.play_button(ctx)?;
+                        self.exit_replay();
+                        self.exit_puzzle();
+                        self.exit_tutorial();
+                        self.close_editor();
+                        self.reset_game();
+                    }
+                }
+                ButtonEventList::Pass if self.tutorial.is_some() => {
+                    //チュートリアルではパスする段階でのみパスできる
+                    if let Some(tutorial) = &mut self.tutorial {
+                        if tutorial.try_pass() {
+                            self.se.play_button(ctx)?;
+                            self.now_user.nextuser();
+                        }
+                    }
                 }
                 ButtonEventList::Pass => {
                     //パスできるのは，プレイヤー側のターンかつおける場所がなかったときのみ
-                    if self.now_user.now as i32 == USER_COLOR as i32
+                    if self.is_playing()
+                        && self.now_user.now as i32 == USER_COLOR as i32
                         && predict::can_set_pos(&self.board, USER_COLOR).len() == 0
                         && !self.is_gameend()
                     {
                         self.se.play_button(ctx)?;
                         self.now_user.nextuser();
+                        self.illegal = None;
                         self.mouse_inf.set_mouseinf(ctx);
                         return Ok(());
                     }
                 }
+                ButtonEventList::Difficulty => {
+                    //次の思考から反映される(思考中の探索はそのまま続ける)
+                    self.se.play_button(ctx)?;
+                    self.settings.difficulty = self.settings.difficulty.next();
+                    self.settings.save();
+                    self.apply_difficulty();
+                }
+                ButtonEventList::Coach => {
+                    self.se.play_button(ctx)?;
+                    self.coach.enabled = !self.coach.enabled;
+                    self.coach.clear();
+                    self.settings.coach = self.coach.enabled;
+                    self.settings.save();
+                    self.set_coach_text();
+                }
+                ButtonEventList::Explain => {
+                    self.se.play_button(ctx)?;
+                    self.settings.explain = !self.settings.explain;
+                    self.settings.save();
+                    self.set_explain_text();
+                }
+                ButtonEventList::Hint => {
+                    //ヒントを出せるのは，プレイヤー側のターンで置ける場所があるときのみ
+                    if self.is_playing()
+                        && self.now_user.now as i32 == USER_COLOR as i32
+                        && predict::can_set_pos(&self.board, USER_COLOR).len() != 0
+                    {
+                        let seed = random::derive(
+                            random::derive(self.seed, HINT_STREAM),
+                            self.tree.depth() as u64,
+                        );
+                        if self.hint.request(&self.board, USER_COLOR, seed) {
+                            self.se.play_button(ctx)?;
+                        }
+                    }
+                }
+                ButtonEventList::Analysis => {
+                    self.se.play_button(ctx)?;
+                    self.analysis.enabled = !self.analysis.enabled;
+                    let text = if self.analysis.enabled {
+                        "Analyze: on"
+                    } else {
+                        "Analyze: off"
+                    };
+                    self.ui.set_text(ButtonEventList::Analysis, text);
+                }
+                ButtonEventList::StepBack | ButtonEventList::StepForward => {
+                    //再生中は一手ずつ進める・戻す
+                    //解析中は盤面に表示する読み筋を一手ずつ進める・戻す
+                    let forward = event == ButtonEventList::StepForward;
+                    if self.replay.is_some() {
+                        if forward {
+                            self.replay_next(ctx)?;
+                        } else {
+                            self.replay_back(ctx)?;
+                        }
+                    } else if let Some(tutorial) = &mut self.tutorial {
+                        //チュートリアル中は前後の段階へ移る
+                        if tutorial.select(if forward { 1 } else { -1 }) {
+                            self.se.play_button(ctx)?;
+                            self.show_tutorial();
+                        }
+                    } else if let Some(puzzle) = &mut self.puzzle {
+                        //詰めオセロ中は前後の問題へ移る
+                        self.se.play_button(ctx)?;
+                        puzzle.select(puzzle.index as i32 + if forward { 1 } else { -1 });
+                        self.show_puzzle();
+                    } else if self.analysis.result().is_some() {
+                        self.se.play_button(ctx)?;
+                        self.analysis.step_pv(if forward { 1 } else { -1 });
+                    }
+                }
+                ButtonEventList::Replay => {
+                    //思考中・石の回転中は対局の状態を退避できないので始めない
+                    if self.replay.is_some() {
+                        self.se.play_button(ctx)?;
+                        self.exit_replay();
+                    } else if self.is_playing() && !self.status.thinking && !self.status.rotating {
+                        match replayviewer::ReplayViewer::load_latest() {
+                            Ok(viewer) => {
+                                self.se.play_button(ctx)?;
+                                self.enter_replay(viewer);
+                            }
+                            Err(e) => eprintln!("{}", e),
+                        }
+                    }
+                }
+                ButtonEventList::Autoplay => {
+                    if let Some(replay) = &mut self.replay {
+                        self.se.play_button(ctx)?;
+                        replay.autoplay = !replay.autoplay;
+                    }
+                }
+                ButtonEventList::MoveBack
+                | ButtonEventList::MoveForward
+                | ButtonEventList::VariationPrev
+                | ButtonEventList::VariationNext
+                | ButtonEventList::DeleteVariation => {
+                    //思考中の局面は動かせない
+                    if self.is_playing() && !self.status.thinking {
+                        let moved = match event {
+                            ButtonEventList::MoveBack => self.tree.back(),
+                            ButtonEventList::MoveForward => self.tree.forward(),
+                            ButtonEventList::VariationPrev => self.tree.switch_variation(-1),
+                            ButtonEventList::VariationNext => self.tree.switch_variation(1),
+                            _ => {
+                                let deleted = self.tree.delete();
+                                if deleted {
+                                    self.recorded = false;
+                                }
+                                deleted
+                            }
+                        };
+                        if moved {
+                            self.se.play_button(ctx)?;
+                            self.show_tree_position();
+                        }
+                    }
+                }
+                ButtonEventList::Promote => {
+                    if self.is_playing() && self.tree.promote() {
+                        self.se.play_button(ctx)?;
+                        self.recorded = false;
+                    }
+                }
+                ButtonEventList::Load => {
+                    if self.is_playing() && !self.status.thinking {
+                        match self.load_game() {
+                            Ok(()) => self.se.play_button(ctx)?,
+                            Err(e) => eprintln!("{}", e),
+                        }
+                    }
+                }
+                ButtonEventList::Edit => {
+                    if self.editor.is_some() {
+                        if self.exit_editor() {
+                            self.se.play_button(ctx)?;
+                        }
+                    } else if self.is_playing() && !self.status.thinking {
+                        self.se.play_button(ctx)?;
+                        self.enter_editor();
+                    }
+                }
+                ButtonEventList::Tutorial => {
+                    if self.tutorial.is_some() {
+                        self.se.play_button(ctx)?;
+                        self.exit_tutorial();
+                    } else if self.is_playing() && !self.status.thinking && !self.status.rotating {
+                        self.se.play_button(ctx)?;
+                        self.enter_tutorial();
+                    }
+                }
+                ButtonEventList::Puzzle => {
+                    if self.puzzle.is_some() {
+                        self.se.play_button(ctx)?;
+                        self.exit_puzzle();
+                    } else if self.is_playing() && !self.status.thinking && !self.status.rotating {
+                        match puzzle::PuzzleMode::load() {
+                            Ok(mode) => {
+                                self.se.play_button(ctx)?;
+                                self.enter_puzzle(mode);
+                            }
+                            Err(e) => eprintln!("{}", e),
+                        }
+                    }
+                }
+                ButtonEventList::EditTurn
+                | ButtonEventList::EditClear
+                | ButtonEventList::EditInitial => {
+                    if let Some(editor) = &mut self.editor {
+                        self.se.play_button(ctx)?;
+                        match event {
+                            ButtonEventList::EditTurn => {
+                                self.now_user.now.reversestone();
+                                self.set_turn_text();
+                            }
+                            ButtonEventList::EditClear => editor.clear(&mut self.board),
+                            _ => {
+                                self.board = board::Board::new(
+                                    BOARDSIZE,
+                                    self.window_width,
+                                    self.window_height,
+                                );
+                                self.now_user.now = Stone::White;
+                                self.set_turn_text();
+                            }
+                        }
+                    }
+                }
+                ButtonEventList::Graph => {
+                    //評価値のグラフは対局が終わってから表示できる
+                    if self.is_playing() && self.status.game_end {
+                        self.se.play_button(ctx)?;
+                        self.graph.visible = !self.graph.visible;
+                        self.graph.view = None;
+                    }
+                }
+                ButtonEventList::Review => {
+                    //グラフを表示し，プレイヤーの緩手以上の手を順に盤面に表示する
+                    if self.is_playing() && self.status.game_end {
+                        self.se.play_button(ctx)?;
+                        self.graph.visible = true;
+                        let reviewed = self.graph.review(USER_COLOR);
+                        self.graph.view_next_reviewed(&reviewed);
+                    }
+                }
             };
         }
-        //プレイヤー側の石置き判定　CPUが思考中だった場合はreturn
-        match self.cpu_set_stone(ctx, CPU_COLOR) {
-            None => {}
-            Some(_) => {
-                return Ok(());
+        //再生中は石を置けないので，自動再生のみ行う
+        if let Some(replay) = &mut self.replay {
+            let title = format!(
+                "MyOthelloGame (replay seed {} {}/{})",
+                replay.record.seed,
+                replay.index,
+                replay.moves.len()
+            );
+            graphics::set_window_title(ctx, &title);
+            let next = replay.update(ggez::timer::delta(ctx).as_secs_f32(), self.status.rotating);
+            let text = if replay.autoplay {
+                "Auto: on"
+            } else {
+                "Auto: off"
+            };
+            self.ui.set_text(ButtonEventList::Autoplay, text);
+            if next {
+                self.replay_next(ctx)?;
             }
+            self.mouse_inf.set_mouseinf(ctx);
+            self.board.count_stone();
+            return Ok(());
+        }
+        //盤面編集中はクリックしたマスの石を変える
+        if let Some(editor) = &mut self.editor {
+            if self.mouse_inf.is_clicked(ctx) {
+                if let Some(t) = self
+                    .board
+                    .screencoordinate_to_boardcoordinate(ggez::input::mouse::position(ctx))
+                {
+                    editor.cycle(&mut self.board, (t.0 as usize, t.1 as usize));
+                    self.se.play_stone(ctx)?;
+                }
+            }
+            self.mouse_inf.set_mouseinf(ctx);
+            self.board.count_stone();
+            return Ok(());
+        }
+        //チュートリアル中は指示通りのマスがクリックされたら石を置く
+        if let Some(tutorial) = &mut self.tutorial {
+            if self.mouse_inf.is_clicked(ctx) && !self.status.rotating {
+                if let Some(t) = self
+                    .board
+                    .screencoordinate_to_boardcoordinate(ggez::input::mouse::position(ctx))
+                {
+                    let pos = (t.0 as usize, t.1 as usize);
+                    if tutorial.try_move(&self.board, pos) {
+                        self.play_with_effects(ctx, self.now_user.now, pos)?;
+                    }
+                }
+            }
+            self.mouse_inf.set_mouseinf(ctx);
+            self.board.count_stone();
+            return Ok(());
+        }
+        //詰めオセロ中はクリックしたマスを解答とする(石の回転中は次の解答を受け付けない)
+        if let Some(puzzle) = &mut self.puzzle {
+            puzzle.update();
+            if self.mouse_inf.is_clicked(ctx) && !self.status.rotating {
+                if let Some(t) = self
+                    .board
+                    .screencoordinate_to_boardcoordinate(ggez::input::mouse::position(ctx))
+                {
+                    self.puzzle_click(ctx, (t.0 as usize, t.1 as usize))?;
+                }
+            }
+            self.mouse_inf.set_mouseinf(ctx);
+            self.board.count_stone();
+            return Ok(());
+        }
+        //グラフがクリックされたら，その局面を盤面に表示する
+        if self.mouse_inf.is_clicked(ctx) {
+            if let Some(index) = self.graph.index_at(ggez::input::mouse::position(ctx)) {
+                self.graph.view = Some(index);
+            }
+        }
+        //ヒントの探索結果の受け取り
+        self.hint.update(&self.board, USER_COLOR);
+        let text = if self.hint.thinking() {
+            "Hint..."
+        } else {
+            "Hint"
         };
+        self.ui.set_text(ButtonEventList::Hint, text);
+        //コーチモードではプレイヤー側のターンの局面を探索しておき，警告に使う
+        self.coach.update(&self.board, USER_COLOR);
+        if self.now_user.now as i32 == USER_COLOR as i32 && !self.is_gameend() {
+            let seed = random::derive(
+                random::derive(self.seed, COACH_STREAM),
+                self.tree.depth() as u64,
+            );
+            self.coach.request(&self.board, USER_COLOR, seed);
+        }
+        //解析モードではプレイヤー側のターンの局面を探索し続ける
+        if self.analysis.enabled
+            && self.now_user.now as i32 == USER_COLOR as i32
+            && !self.is_gameend()
+        {
+            self.analysis.start(&self.board, USER_COLOR, self.seed);
+        } else {
+            self.analysis.stop();
+        }
+        //プレイヤー側の石置き判定　CPUが思考中だった場合はreturn
+        //棋譜の木で次の手がある局面を表示している間は，CPUは打たずに待つ
+        if !self.tree.has_next() {
+            match self.cpu_set_stone(ctx, CPU_COLOR) {
+                None => {}
+                Some(_) => {
+                    return Ok(());
+                }
+            };
+        }
         //プレイヤー側の石置き判定
         match self.player_set_stone(ctx, USER_COLOR) {
             None => {}
@@ -458,23 +1408,136 @@ impl EventHandler for MyGame {
             ゲーム中はパーティクルはコマの下へ描画
             ゲームが終わったら優先度を上げる
         */
-        if !self.is_gameend() {
+        //再生中は記録の終局で対局を終えた扱いにしない
+        let gameend = self.is_playing() && self.is_gameend();
+        if !gameend {
             self.particles.draw(ctx)?;
         }
-        //枠と石の描画
-        self.board.draw(ctx, &self.rotationrecord)?;
-        //置ける候補の描画
-        self.board.draw_candidate(ctx, self.now_user.now)?;
-        self.draw_can_rotate(ctx)?;
-        self.draw_ui(ctx)?;
-        if self.is_gameend() {
-            if !self.status.game_end && self.board.return_win() as i32 == USER_COLOR as i32 {
-                self.particles.create_confetti();
+        //枠と石の描画(グラフで選んだ局面を表示中はその局面を描画)
+        match self.graph.view_board(self.window_width, self.window_height) {
+            Some(mut board) => {
+                board.draw(ctx, &[[0.0; BOARDSIZE]; BOARDSIZE])?;
+                //振り返りで判定された手の後の局面であれば，その手とより良い手を示す
+                let reviewed = self.graph.review(USER_COLOR);
+                let view = self.graph.view.unwrap_or(0);
+                if let Some(m) = reviewed.iter().find(|m| m.index + 1 == view) {
+                    review::draw_move(ctx, &self.font, m)?;
+                }
+            }
+            None => {
+                self.board.draw(ctx, &self.rotationrecord)?;
+                //置ける候補の描画(再生中は置けないので描画しない)
+                if self.is_playing() {
+                    self.board.draw_candidate(ctx, self.now_user.now)?;
+                    self.draw_next_moves(ctx)?;
+                } else if let Some(false) = self.tutorial.as_ref().map(|t| t.done) {
+                    self.board.draw_candidate(ctx, self.now_user.now)?;
+                }
             }
-            self.status.game_end = true;
+        }
+        if let Some(result) = self.analysis.result() {
+            //完全読みの結果は石数差，それ以外は勝率と試行回数を表示
+            let caption = if result.moves.iter().any(|m| m.score.is_some()) {
+                format!("解析 完全読み 読み筋{}手", result.pv.len())
+            } else {
+                format!("解析 {}回 読み筋{}手", result.iterations, result.pv.len())
+            };
+            self.board.draw_move_stats(
+                ctx,
+                &self.font,
+                &result.moves,
+                &|m| match m.score {
+                    Some(score) => format!("{:+}", score),
+                    None => format!("{:.0}%\n{}", m.win_rate * 100.0, m.visits),
+                },
+                graphics::Color::new(1.0, 0.8, 0.1, 1.0),
+                &caption,
+            )?;
+            self.board.draw_pv(
+                ctx,
+                &self.font,
+                &result.pv,
+                USER_COLOR,
+                self.analysis.pv_step,
+            )?;
+        }
+        if let Some(editor) = &self.editor {
+            editor.draw(ctx, &self.font)?;
+        }
+        if let Some(puzzle) = &self.puzzle {
+            puzzle.draw(ctx, &self.font)?;
+        }
+        if let Some(tutorial) = &self.tutorial {
+            //指定したマスに置いたときにひっくり返る石を示す
+            if let Some(target) = tutorial.target() {
+                let cell_size = self.window_width as f32 / BOARDSIZE as f32;
+                self.board.draw_placed_rotation_stone(
+                    ctx,
+                    &predict::can_cnt(&self.board.gamebord, self.now_user.now, target, false),
+                    self.status.get_placed_rotating_stone_count(),
+                    cell_size,
+                )?;
+            }
+            tutorial.draw(ctx, &self.font)?;
+        }
+        if let Some(illegal) = &self.illegal {
+            if self.is_playing() {
+                illegal.draw(ctx, &self.font)?;
+            }
+        } else if self.is_playing() && self.now_user.now as i32 == USER_COLOR as i32 && !gameend {
+            //コーチの警告がある間は，CPUの手の説明より警告を優先する
+            let hover = self
+                .board
+                .screencoordinate_to_boardcoordinate(self.mouse_inf.pos)
+                .map(|t| (t.0 as usize, t.1 as usize));
+            if self.coach.shown(&self.board, USER_COLOR, hover).is_some() {
+                self.coach
+                    .draw(ctx, &self.font, &self.board, USER_COLOR, hover)?;
+            } else if let (true, Some(explanation)) = (self.settings.explain, &self.explanation) {
+                explanation.draw(ctx, &self.font)?;
+            }
+        }
+        self.draw_search_progress(ctx)?;
+        if self.is_playing() || self.tutorial.is_some() {
+            self.draw_can_rotate(ctx)?;
+        }
+        self.draw_hint(ctx)?;
+        self.draw_ui(ctx)?;
+        if gameend {
             self.particles.draw(ctx)?;
-            self.draw_win_or_lose(ctx)?;
+            if self.graph.visible {
+                let reviewed = self.graph.review(USER_COLOR);
+                self.graph.draw(ctx, &self.font, &reviewed)?;
+            } else {
+                self.draw_win_or_lose(ctx)?;
+            }
         }
         graphics::present(ctx)
     }
 }
+/*
+    盤面の上側に半透明の帯を敷き，textを描画する(盤面編集・詰めオセロの説明用)
+*/
+fn draw_banner(
+    ctx: &mut Context,
+    font: &graphics::Font,
+    text: &str,
+    color: graphics::Color,
+) -> GameResult<()> {
+    let text = graphics::Text::new((text, *font, BANNER_FONT_SIZE));
+    let rect = graphics::Rect {
+        x: 0.0,
+        y: 0.0,
+        w: crate::WIDTH,
+        h: text.height(ctx) + 8.0,
+    };
+    let panel = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        rect,
+        graphics::Color::new(0.0, 0.0, 0.0, 0.6),
+    )?;
+    graphics::draw(ctx, &panel, (Vec2::new(0.0, 0.0),))?;
+    graphics::draw(ctx, &text, (Vec2::new(8.0, 4.0), color))?;
+    Ok(())
+}
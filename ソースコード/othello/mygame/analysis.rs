@@ -0,0 +1,92 @@
+use crate::mygame::predict::{self, montecarlo::MoveStat, EngineConfig};
+use crate::mygame::{random, Board, Stone};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/*
+    解析モード
+    現在の局面を別スレッドでモンテカルロ木探索し続け，候補手ごとの勝率と試行回数，読み筋を随時共有する
+    空きマスが少ない局面では完全読みを行い，候補手ごとの石数差を求める
+    局面が変わったら前の探索を止めて新しい局面の探索を始める
+*/
+const ANALYSIS_MAXTRY: usize = 1_000_000; //一つの局面を探索し続ける上限
+const PV_DEFAULT_STEPS: usize = 4; //局面が変わったときに盤面に表示する読み筋の手数
+
+#[derive(Clone, Default)]
+pub struct AnalysisResult {
+    pub moves: Vec<MoveStat>,
+    pub iterations: usize,
+    pub pv: Vec<(usize, usize)>, //読み筋(SearchStats::pv)
+}
+
+pub struct Analysis {
+    pub enabled: bool,
+    key: Option<u64>,   //探索中の局面(Board::hash)
+    pub pv_step: usize, //盤面に表示する読み筋の手数
+    result: Arc<Mutex<AnalysisResult>>,
+    stop: Arc<AtomicBool>,
+}
+impl Analysis {
+    pub fn new() -> Analysis {
+        Analysis {
+            enabled: false,
+            key: None,
+            pv_step: PV_DEFAULT_STEPS,
+            result: Arc::new(Mutex::new(AnalysisResult::default())),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    /*
+        colorの手番のboardを探索する．既に同じ局面を探索中であれば何もしない
+        seedは対局のシード(解析は対局の乱数列とは別の乱数列を使う)
+    */
+    pub fn start(&mut self, board: &Board, color: Stone, seed: u64) {
+        let key = board.hash(color);
+        if self.key == Some(key) {
+            return;
+        }
+        self.stop();
+        self.key = Some(key);
+        self.pv_step = PV_DEFAULT_STEPS;
+        //前の探索の結果を書き込まれないよう，結果と停止フラグは局面ごとに作り直す
+        let result = Arc::new(Mutex::new(AnalysisResult::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        self.result = result.clone();
+        self.stop = stop.clone();
+        let board = *board;
+        let mut config = EngineConfig::default();
+        config.maxtry = ANALYSIS_MAXTRY;
+        config.book = false;
+        config.endgame = true;
+        let mut random = random::from_seed(random::derive(seed, key));
+        thread::spawn(move || {
+            predict::search_with_report(&board, color, &config, &mut random, &mut |stats| {
+                *result.lock().unwrap() = AnalysisResult {
+                    moves: stats.children.clone(),
+                    iterations: stats.iterations,
+                    pv: stats.pv.clone(),
+                };
+                !stop.load(Ordering::Relaxed)
+            });
+        });
+    }
+    //探索中であれば止める
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.key = None;
+    }
+    /*
+        盤面に表示する読み筋の手数をdeltaだけ進める(負なら戻す)
+        0手から最新の読み筋の長さまでの範囲に収める
+    */
+    pub fn step_pv(&mut self, delta: i32) {
+        let length = self.result().map_or(0, |result| result.pv.len());
+        let step = std::cmp::min(self.pv_step, length) as i32 + delta;
+        self.pv_step = std::cmp::max(0, std::cmp::min(step, length as i32)) as usize;
+    }
+    //探索中の局面の最新の結果．探索していない場合はNone
+    pub fn result(&self) -> Option<AnalysisResult> {
+        self.key.map(|_| self.result.lock().unwrap().clone())
+    }
+}
@@ -1,7 +1,10 @@
-use crate::mygame::{predict, Stone, BOARDSIZE};
+use crate::mygame::predict::{self, montecarlo::MoveStat};
+use crate::mygame::{Stone, BOARDSIZE};
 use ggez::{graphics, Context, GameResult};
 use glam::*;
 use mint::Point2;
+const ANALYSIS_FONT_SIZE: f32 = 15.0; //探索の統計の文字の大きさ
+const PV_FONT_SIZE: f32 = 24.0; //読み筋の手順の番号の大きさ
 #[derive(Clone, Copy)]
 pub struct Board {
     pub gamebord: [[Stone; BOARDSIZE]; BOARDSIZE],
@@ -10,6 +13,34 @@ pub struct Board {
     pub white_num: u32,
     window_width: u32,
     window_height: u32,
+    hash: u64, //石の配置のZobristハッシュ(手番は含まない)
+}
+/*
+    Zobristハッシュ用の乱数表 [行][列][0:黒 1:白]
+    SplitMix64で決まった値を生成するので，実行ごとに変わらない
+*/
+const ZOBRIST: [[[u64; 2]; BOARDSIZE]; BOARDSIZE] = zobrist_table();
+const ZOBRIST_WHITE_TO_MOVE: u64 = 0x6a09_e667_f3bc_c908; //手番が白の場合に重ねる値
+const fn zobrist_table() -> [[[u64; 2]; BOARDSIZE]; BOARDSIZE] {
+    let mut table = [[[0; 2]; BOARDSIZE]; BOARDSIZE];
+    let mut state: u64 = 0x0123_4567_89ab_cdef;
+    let mut i = 0;
+    while i < BOARDSIZE * BOARDSIZE * 2 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i / 2 / BOARDSIZE][i / 2 % BOARDSIZE][i % 2] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+fn zobrist_key(pos: (usize, usize), stone: Stone) -> u64 {
+    match stone {
+        Stone::Black => ZOBRIST[pos.0][pos.1][0],
+        Stone::White => ZOBRIST[pos.0][pos.1][1],
+        Stone::Blank => 0,
+    }
 }
 impl Board {
     /*初期宣言 */
@@ -26,6 +57,7 @@ impl Board {
             white_num: 0,
             window_width: width,
             window_height: height,
+            hash: Board::full_hash(&v),
         }
     }
     /*
@@ -284,6 +316,122 @@ impl Board {
         }
         Ok(())
     }
+    /*
+        探索中の候補手ごとの統計を候補の円の上に描画する(解析モード・CPUの思考中の表示)
+        各候補手にlabelの文字列を書き，試行回数が最も多い手(完全読みの場合は石数差が最も大きい手)を
+        ring_colorの円で囲む
+        captionは盤面の左上に表示する
+    */
+    pub fn draw_move_stats(
+        &self,
+        ctx: &mut Context,
+        font: &graphics::Font,
+        stats: &[MoveStat],
+        label: &dyn Fn(&MoveStat) -> String,
+        ring_color: graphics::Color,
+        caption: &str,
+    ) -> GameResult<()> {
+        let cell_size =
+            std::cmp::min(self.window_width, self.window_height) as f32 / self.size as f32;
+        let best = if stats.iter().any(|m| m.score.is_some()) {
+            stats.iter().max_by_key(|m| m.score).map(|m| m.pos)
+        } else {
+            stats.iter().max_by_key(|m| m.visits).map(|m| m.pos)
+        };
+        for stat in stats.iter() {
+            let center = Vec2::new(
+                stat.pos.1 as f32 * cell_size + cell_size / 2.0,
+                stat.pos.0 as f32 * cell_size + cell_size / 2.0,
+            );
+            if Some(stat.pos) == best {
+                let ring = graphics::Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::stroke(3.0),
+                    Vec2::new(0.0, 0.0),
+                    cell_size / 2.3,
+                    0.1,
+                    ring_color,
+                )?;
+                graphics::draw(ctx, &ring, (center,))?;
+            }
+            let text = graphics::Text::new((label(stat), *font, ANALYSIS_FONT_SIZE));
+            let (w, h) = (text.width(ctx), text.height(ctx));
+            graphics::draw(
+                ctx,
+                &text,
+                (
+                    center - Vec2::new(w / 2.0, h / 2.0),
+                    graphics::Color::new(1.0, 1.0, 1.0, 0.9),
+                ),
+            )?;
+        }
+        let text = graphics::Text::new((caption.to_string(), *font, ANALYSIS_FONT_SIZE));
+        graphics::draw(
+            ctx,
+            &text,
+            (
+                Vec2::new(2.0, 0.0),
+                graphics::Color::new(1.0, 1.0, 1.0, 0.7),
+            ),
+        )?;
+        Ok(())
+    }
+    /*
+        読み筋(colorの手番から始まる手順)のうち最初のsteps手を，手順の番号を書いた半透明の石で描画する
+        途中でパスが必要な場合は相手の手として扱い，置けない手があればそこで止める
+    */
+    pub fn draw_pv(
+        &self,
+        ctx: &mut Context,
+        font: &graphics::Font,
+        pv: &[(usize, usize)],
+        color: Stone,
+        steps: usize,
+    ) -> GameResult<()> {
+        let cell_size =
+            std::cmp::min(self.window_width, self.window_height) as f32 / self.size as f32;
+        let (mut board, mut color) = (*self, color);
+        for (i, &pos) in pv.iter().take(steps).enumerate() {
+            if predict::can_set_pos(&board, color).len() == 0 {
+                color = color.return_reverse_color(); //パス
+            }
+            if !predict::put_stone(&mut board, color, pos) {
+                break;
+            }
+            let (stone_color, text_color) = match color {
+                Stone::Black => (
+                    graphics::Color::new(0.0, 0.0, 0.0, 0.55),
+                    graphics::Color::WHITE,
+                ),
+                _ => (
+                    graphics::Color::new(1.0, 1.0, 1.0, 0.55),
+                    graphics::Color::BLACK,
+                ),
+            };
+            let center = Vec2::new(
+                pos.1 as f32 * cell_size + cell_size / 2.0,
+                pos.0 as f32 * cell_size + cell_size / 2.0,
+            );
+            let circle = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::fill(),
+                Vec2::new(0.0, 0.0),
+                cell_size / 2.6,
+                0.1,
+                stone_color,
+            )?;
+            graphics::draw(ctx, &circle, (center,))?;
+            let text = graphics::Text::new(((i + 1).to_string(), *font, PV_FONT_SIZE));
+            let (w, h) = (text.width(ctx), text.height(ctx));
+            graphics::draw(
+                ctx,
+                &text,
+                (center - Vec2::new(w / 2.0, h / 2.0), text_color),
+            )?;
+            color = color.return_reverse_color();
+        }
+        Ok(())
+    }
     pub fn draw_placed_rotation_stone(
         &self,
         ctx: &mut Context,
@@ -400,7 +548,123 @@ impl Board {
     }
     pub fn setstone(&mut self, list: &Vec<(usize, usize)>, color: Stone) {
         for pos in list {
+            //変わるマスの分だけハッシュを差分で更新する
+            self.hash ^= zobrist_key(*pos, self.gamebord[pos.0][pos.1]) ^ zobrist_key(*pos, color);
             self.gamebord[pos.0][pos.1] = color;
         }
     }
+    /*
+        colorの手番である盤面のZobristハッシュ
+        同じ石の配置でも手番が異なれば別の値になる
+    */
+    pub fn hash(&self, color: Stone) -> u64 {
+        match color {
+            Stone::White => self.hash ^ ZOBRIST_WHITE_TO_MOVE,
+            _ => self.hash,
+        }
+    }
+    /*
+        石の配置からハッシュを計算し直す(setstoneを通さずに盤面を書き換えた場合用)
+    */
+    fn full_hash(gamebord: &[[Stone; BOARDSIZE]; BOARDSIZE]) -> u64 {
+        let mut hash = 0;
+        for y in 0..BOARDSIZE {
+            for x in 0..BOARDSIZE {
+                hash ^= zobrist_key((y, x), gamebord[y][x]);
+            }
+        }
+        hash
+    }
+    /*
+        盤面を64文字の文字列へと変換する
+        左上(a1)から行ごとに，黒:X 白:O 空き:-
+    */
+    pub fn to_text(&self) -> String {
+        let mut ret = String::with_capacity(BOARDSIZE * BOARDSIZE);
+        for row in self.gamebord.iter() {
+            for stone in row.iter() {
+                ret.push(match stone {
+                    Stone::Black => 'X',
+                    Stone::White => 'O',
+                    Stone::Blank => '-',
+                });
+            }
+        }
+        ret
+    }
+    /*
+        to_textの形式の文字列から盤面を作成する
+        空きマスには'.'も受け付ける
+    */
+    pub fn from_text(text: &str, width: u32, height: u32) -> Option<Board> {
+        let cells: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if cells.len() != BOARDSIZE * BOARDSIZE {
+            return None;
+        }
+        let mut board = Board::new(BOARDSIZE, width, height);
+        for (i, c) in cells.iter().enumerate() {
+            board.gamebord[i / BOARDSIZE][i % BOARDSIZE] = match c {
+                'X' | 'x' | '*' => Stone::Black,
+                'O' | 'o' => Stone::White,
+                '-' | '.' => Stone::Blank,
+                _ => return None,
+            };
+        }
+        board.hash = Board::full_hash(&board.gamebord);
+        board.count_stone();
+        Some(board)
+    }
+    /*
+        盤面座標(行,列)を"d3"のような棋譜の表記へと変換する
+    */
+    pub fn pos_to_notation(pos: (usize, usize)) -> String {
+        format!("{}{}", (b'a' + pos.1 as u8) as char, pos.0 + 1)
+    }
+    /*
+        "d3"のような棋譜の表記を盤面座標(行,列)へと変換する
+    */
+    pub fn notation_to_pos(text: &str) -> Option<(usize, usize)> {
+        let bytes = text.as_bytes();
+        if bytes.len() != 2 {
+            return None;
+        }
+        let col = (bytes[0].to_ascii_lowercase() as i32) - ('a' as i32);
+        let row = (bytes[1] as i32) - ('1' as i32);
+        if col < 0 || BOARDSIZE as i32 <= col || row < 0 || BOARDSIZE as i32 <= row {
+            return None;
+        }
+        Some((row as usize, col as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mygame::predict;
+
+    fn initial_board() -> Board {
+        let mut board = Board::new(BOARDSIZE, 0, 0);
+        board.count_stone();
+        board
+    }
+
+    #[test]
+    fn incremental_hash_matches_full_hash() {
+        //数手打つ間，差分で更新したハッシュが計算し直したものと一致する
+        let mut board = initial_board();
+        let mut color = Stone::White;
+        for _ in 0..10 {
+            let pos = predict::can_set_pos(&board, color)[0];
+            board.setstone(&predict::can_cnt(&board.gamebord, color, pos, false), color);
+            assert_eq!(board.hash, Board::full_hash(&board.gamebord));
+            color = color.return_reverse_color();
+        }
+        let parsed = Board::from_text(&board.to_text(), 0, 0).unwrap();
+        assert_eq!(parsed.hash(color), board.hash(color));
+    }
+    #[test]
+    fn side_to_move_changes_hash() {
+        let board = initial_board();
+        assert_ne!(board.hash(Stone::White), board.hash(Stone::Black));
+    }
 }
@@ -3,6 +3,21 @@ use ggez::{graphics, Context, GameResult};
 use glam::*;
 use mint;
 const BUTTON_ROUND: f32 = 10.0;
+/*
+    盤面の下に並べる小さいボタンの行
+    盤面は正方形なので，盤面の下端はウィンドウの幅と同じ位置になる
+    一行にTOOLBAR_SLOTS個並べ，TOOLBAR_ROWS行まで使う
+*/
+const TOOLBAR_TOP: f32 = crate::WIDTH + 2.0;
+const TOOLBAR_HEIGHT: f32 = 32.0;
+const TOOLBAR_WIDTH: f32 = 115.0; //4つ並べてウィンドウの幅に収まる大きさ
+const TOOLBAR_MARGIN: f32 = 4.0;
+const TOOLBAR_FONT_SIZE: f32 = 18.0;
+const TOOLBAR_SLOTS: usize = 4;
+const TOOLBAR_ROWS: usize = 5;
+//ツールバーの下端(この下に他の表示を置く)
+pub const TOOLBAR_BOTTOM: f32 =
+    TOOLBAR_TOP + TOOLBAR_ROWS as f32 * (TOOLBAR_HEIGHT + TOOLBAR_MARGIN) - TOOLBAR_MARGIN;
 /*
     UIとしてクリックするボタンを管理するTrait
 */
@@ -14,7 +29,7 @@ impl UIs {
         パスボタンとリセットボタンを宣言し，Box化して配列として持つ
     */
     pub fn new() -> UIs {
-        UIs {
+        let mut ui = UIs {
             buttons: vec![
                 Box::new(Reset::new(
                     mint::Point2 {
@@ -38,7 +53,81 @@ impl UIs {
                     },
                     "pass",
                 )),
+                Box::new(ToolButton::new(0, "", ButtonEventList::Difficulty)),
+                Box::new(ToolButton::new(1, "", ButtonEventList::Analysis)),
+                Box::new(ToolButton::new(2, "Hint", ButtonEventList::Hint)),
+                Box::new(ToolButton::half(3, 0, "PV <", ButtonEventList::StepBack)),
+                Box::new(ToolButton::half(3, 1, "PV >", ButtonEventList::StepForward)),
+                Box::new(ToolButton::new(4, "Graph", ButtonEventList::Graph)),
+                Box::new(ToolButton::new(5, "Review", ButtonEventList::Review)),
+                Box::new(ToolButton::new(6, "Replay", ButtonEventList::Replay)),
+                Box::new(ToolButton::new(7, "Auto: off", ButtonEventList::Autoplay)),
+                Box::new(ToolButton::half(8, 0, "Back", ButtonEventList::MoveBack)),
+                Box::new(ToolButton::half(8, 1, "Next", ButtonEventList::MoveForward)),
+                Box::new(ToolButton::half(
+                    9,
+                    0,
+                    "Var <",
+                    ButtonEventList::VariationPrev,
+                )),
+                Box::new(ToolButton::half(
+                    9,
+                    1,
+                    "Var >",
+                    ButtonEventList::VariationNext,
+                )),
+                Box::new(ToolButton::half(10, 0, "Main", ButtonEventList::Promote)),
+                Box::new(ToolButton::half(
+                    10,
+                    1,
+                    "Del",
+                    ButtonEventList::DeleteVariation,
+                )),
+                Box::new(ToolButton::new(11, "Load", ButtonEventList::Load)),
+                Box::new(ToolButton::new(12, "Edit", ButtonEventList::Edit)),
+                Box::new(ToolButton::new(13, "Turn", ButtonEventList::EditTurn)),
+                Box::new(ToolButton::half(14, 0, "Clear", ButtonEventList::EditClear)),
+                Box::new(ToolButton::half(
+                    14,
+                    1,
+                    "Init",
+                    ButtonEventList::EditInitial,
+                )),
+                Box::new(ToolButton::new(15, "Puzzle", ButtonEventList::Puzzle)),
+                Box::new(ToolButton::new(16, "Tutorial", ButtonEventList::Tutorial)),
+                Box::new(ToolButton::new(17, "Coach: off", ButtonEventList::Coach)),
+                Box::new(ToolButton::new(
+                    18,
+                    "Explain: off",
+                    ButtonEventList::Explain,
+                )),
             ],
+        };
+        //盤面編集のボタンは編集中のみ表示する
+        ui.set_visible(ButtonEventList::EditTurn, false);
+        ui.set_visible(ButtonEventList::EditClear, false);
+        ui.set_visible(ButtonEventList::EditInitial, false);
+        ui
+    }
+    /*
+        eventを返すボタンの表示する文字列を変更する
+    */
+    pub fn set_text(&mut self, event: ButtonEventList, text: &str) {
+        for button in self.buttons.iter_mut() {
+            if button.action().ok() == Some(event) {
+                button.set_text(text);
+            }
+        }
+    }
+    /*
+        eventを返すボタンを表示するかを変更する
+        表示しないボタンは描画せず，クリックもできない
+    */
+    pub fn set_visible(&mut self, event: ButtonEventList, visible: bool) {
+        for button in self.buttons.iter_mut() {
+            if button.action().ok() == Some(event) {
+                button.set_visible(visible);
+            }
         }
     }
 }
@@ -52,8 +141,11 @@ pub trait Button {
         /*
             クリックされてたらSome(true)
             マウスオーバーのみはSome(false)
-            それ以外はNone
+            それ以外(表示していない場合も)はNone
         */
+        if !self.visible() {
+            return None;
+        }
         let over = self.poslu().x <= left_click.pos.x
             && self.poslu().y <= left_click.pos.y
             && self.posrb().x >= left_click.pos.x
@@ -86,6 +178,17 @@ pub trait Button {
         イベントの種類のみを返して，特別な処理は行わない
     */
     fn action(&self) -> GameResult<ButtonEventList>;
+    /*
+        表示する文字列を変更する(状態によって表示が変わるボタン用)
+    */
+    fn set_text(&mut self, _text: &str) {}
+    /*
+        表示しているか(状態によって隠れるボタン用)
+    */
+    fn visible(&self) -> bool {
+        true
+    }
+    fn set_visible(&mut self, _visible: bool) {}
 }
 struct Reset {
     poslu: mint::Point2<f32>,
@@ -227,3 +330,103 @@ impl Button for Pass {
         Ok(ButtonEventList::Pass)
     }
 }
+
+/*
+    盤面の下のツールバーに並べる小さいボタン
+    押されたときにeventを返す．状態によって変わる表示はUIs::set_text，表示するかはUIs::set_visibleで設定する
+*/
+pub struct ToolButton {
+    poslu: mint::Point2<f32>,
+    posrb: mint::Point2<f32>,
+    text: String,
+    event: ButtonEventList,
+    visible: bool,
+}
+impl ToolButton {
+    //ツールバーのslot番目(左上から0,1,..．TOOLBAR_SLOTS個ごとに次の行)の位置に置く
+    pub fn new(slot: usize, text: &str, event: ButtonEventList) -> ToolButton {
+        let (row, column) = (slot / TOOLBAR_SLOTS, slot % TOOLBAR_SLOTS);
+        let x = TOOLBAR_MARGIN + column as f32 * (TOOLBAR_WIDTH + TOOLBAR_MARGIN);
+        let y = TOOLBAR_TOP + row as f32 * (TOOLBAR_HEIGHT + TOOLBAR_MARGIN);
+        ToolButton {
+            poslu: mint::Point2 { x: x, y: y },
+            posrb: mint::Point2 {
+                x: x + TOOLBAR_WIDTH,
+                y: y + TOOLBAR_HEIGHT,
+            },
+            text: text.to_string(),
+            event: event,
+            visible: true,
+        }
+    }
+    //ツールバーのslot番目を左右に分けた，half番目(0:左 1:右)の位置に置く
+    pub fn half(slot: usize, half: usize, text: &str, event: ButtonEventList) -> ToolButton {
+        let mut button = ToolButton::new(slot, text, event);
+        let width = (TOOLBAR_WIDTH - TOOLBAR_MARGIN) / 2.0;
+        button.poslu.x += half as f32 * (width + TOOLBAR_MARGIN);
+        button.posrb.x = button.poslu.x + width;
+        button
+    }
+}
+impl Button for ToolButton {
+    fn poslu(&self) -> &mint::Point2<f32> {
+        &self.poslu
+    }
+    fn posrb(&self) -> &mint::Point2<f32> {
+        &self.posrb
+    }
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        font: &graphics::Font,
+        mouse: &mouse::MouseInf,
+        _board: &Board,
+    ) -> GameResult<()> {
+        if !self.visible {
+            return Ok(());
+        }
+        let rect_siz = graphics::Rect {
+            x: self.poslu().x,
+            y: self.poslu().y,
+            w: (self.posrb().x - self.poslu().x),
+            h: (self.posrb().y - self.poslu().y),
+        };
+        let color = match self.clicked(mouse, ctx) {
+            Some(false) => graphics::Color::new(0.7, 0.7, 0.7, 0.7),
+            _ => graphics::Color::new(1.0, 1.0, 1.0, 0.7),
+        };
+        let rect = graphics::Mesh::new_rounded_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            rect_siz,
+            BUTTON_ROUND,
+            graphics::Color::WHITE,
+        )?;
+        let text = graphics::Text::new((self.text.to_string(), *font, TOOLBAR_FONT_SIZE));
+        graphics::draw(ctx, &rect, ((Vec2::new(0.0, 0.0)), color))?;
+        graphics::draw(
+            ctx,
+            &text,
+            (
+                (Vec2::new(
+                    rect_siz.center().x - text.width(ctx) / 2.0,
+                    rect_siz.center().y - text.height(ctx) / 1.9,
+                )),
+                graphics::Color::BLACK,
+            ),
+        )?;
+        Ok(())
+    }
+    fn action(&self) -> GameResult<ButtonEventList> {
+        Ok(self.event)
+    }
+    fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+    }
+    fn visible(&self) -> bool {
+        self.visible
+    }
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+}
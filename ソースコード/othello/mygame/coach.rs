@@ -0,0 +1,227 @@
+use crate::mygame::predict::{self, montecarlo::MoveStat, EngineConfig};
+use crate::mygame::review::Severity;
+use crate::mygame::{random, Board, Stone, BOARDSIZE};
+use ggez::{graphics, Context, GameResult};
+use glam::*;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/*
+    コーチモード(初心者向け)
+    プレイヤー側の手番の局面をヒントと同じく別スレッドで探索しておき，
+    マウスオーバーした手・クリックした手が次のいずれかに当たる場合に警告する
+        ・相手に新しく角を取られる
+        ・空いている角の斜め隣(Xマス)に置く
+        ・最善の手より勝率を大きく失う(振り返りの悪手以上)
+    警告した手をクリックした場合はすぐには打たず，同じマスをもう一度クリックしたら打つ
+*/
+const X_SQUARES: [((usize, usize), (usize, usize)); 4] = [
+    ((1, 1), (0, 0)),
+    ((1, 6), (0, 7)),
+    ((6, 1), (7, 0)),
+    ((6, 6), (7, 7)),
+]; //(Xマス，その角)
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Warning {
+    Corner((usize, usize)),  //相手が新しく取れるようになる角
+    XSquare((usize, usize)), //置いたXマスの隣の空いている角
+    EvalLoss {
+        loss: f32,            //失う勝率(0〜1)
+        best: (usize, usize), //探索で最善とした手
+    },
+}
+impl Warning {
+    pub fn message(&self) -> String {
+        match self {
+            Warning::Corner(corner) => {
+                format!("相手に角{}を取られます", Board::pos_to_notation(*corner))
+            }
+            Warning::XSquare(corner) => format!(
+                "空いている角{}の斜め隣(Xマス)です",
+                Board::pos_to_notation(*corner)
+            ),
+            Warning::EvalLoss { loss, best } => format!(
+                "最善の{}より勝率が{:.0}%下がります",
+                Board::pos_to_notation(*best),
+                loss * 100.0
+            ),
+        }
+    }
+}
+
+pub struct Coach {
+    pub enabled: bool,
+    key: Option<u64>, //探索した局面(Board::hash)
+    receiver: Option<Receiver<Vec<MoveStat>>>,
+    stats: Option<Vec<MoveStat>>,    //候補手ごとの勝率
+    pending: Option<(usize, usize)>, //警告して，打つかの確認を待っている手
+}
+impl Coach {
+    pub fn new(enabled: bool) -> Coach {
+        Coach {
+            enabled: enabled,
+            key: None,
+            receiver: None,
+            stats: None,
+            pending: None,
+        }
+    }
+    /*
+        colorの手番のboardを探索し始める
+        コーチモードでない場合と，既に同じ局面を探索した(している)場合は何もしない
+    */
+    pub fn request(&mut self, board: &Board, color: Stone, seed: u64) {
+        let key = board.hash(color);
+        if !self.enabled || self.key == Some(key) {
+            return;
+        }
+        self.clear();
+        self.key = Some(key);
+        let board = *board;
+        let mut config = EngineConfig::default();
+        config.book = false;
+        config.endgame = true;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let stats = predict::search(&board, color, &config, &mut random::from_seed(seed));
+            let _ = sender.send(stats.children);
+        });
+        self.receiver = Some(receiver);
+    }
+    /*
+        毎フレーム呼ぶ．探索が終わっていれば結果を受け取り，局面が変わっていれば結果と確認待ちの手を消す
+    */
+    pub fn update(&mut self, board: &Board, color: Stone) {
+        if self.key.is_some() && self.key != Some(board.hash(color)) {
+            self.clear();
+            return;
+        }
+        if let Some(receiver) = &self.receiver {
+            if let Ok(stats) = receiver.try_recv() {
+                self.stats = Some(stats);
+                self.receiver = None;
+            }
+        }
+    }
+    //探索の結果と確認待ちの手を消す(探索中の結果は捨てる)
+    pub fn clear(&mut self) {
+        self.key = None;
+        self.receiver = None;
+        self.stats = None;
+        self.pending = None;
+    }
+    /*
+        colorがboardのposに置いたときの警告
+        勝率の警告は探索が終わってから出す．コーチモードでない場合と置けない場所の場合は空
+    */
+    pub fn warnings(&self, board: &Board, color: Stone, pos: (usize, usize)) -> Vec<Warning> {
+        let mut ret = Vec::new();
+        let mut after = *board;
+        if !self.enabled || !predict::put_stone(&mut after, color, pos) {
+            return ret;
+        }
+        //置く前から取れる角は，この手のせいではないので警告しない
+        let opponent = color.return_reverse_color();
+        let before = predict::can_set_pos(board, opponent);
+        for corner in predict::can_set_pos(&after, opponent) {
+            if is_corner(corner) && !before.contains(&corner) {
+                ret.push(Warning::Corner(corner));
+            }
+        }
+        for &(x, corner) in X_SQUARES.iter() {
+            if x == pos && board.gamebord[corner.0][corner.1] as i32 == Stone::Blank as i32 {
+                ret.push(Warning::XSquare(corner));
+            }
+        }
+        if let Some(stats) = &self.stats {
+            let best = stats
+                .iter()
+                .max_by(|a, b| a.win_rate.partial_cmp(&b.win_rate).unwrap());
+            if let (Some(best), Some(stat)) = (best, stats.iter().find(|m| m.pos == pos)) {
+                let loss = best.win_rate - stat.win_rate;
+                match Severity::from_loss(loss) {
+                    Some(Severity::Mistake) | Some(Severity::Blunder) => {
+                        ret.push(Warning::EvalLoss {
+                            loss: loss,
+                            best: best.pos,
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+        ret
+    }
+    /*
+        colorがposに置こうとしたときに呼ぶ．打ってよければtrue
+        警告がある手は一度目は確認待ちにしてfalseを返し，同じ手をもう一度選んだらtrueを返す
+    */
+    pub fn confirm(&mut self, board: &Board, color: Stone, pos: (usize, usize)) -> bool {
+        if self.pending == Some(pos) || self.warnings(board, color, pos).len() == 0 {
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some(pos);
+        false
+    }
+    //警告を表示する手(確認待ちの手，なければマウスオーバーしているマスhover)．警告がなければNone
+    pub fn shown(
+        &self,
+        board: &Board,
+        color: Stone,
+        hover: Option<(usize, usize)>,
+    ) -> Option<(usize, usize)> {
+        let pos = self.pending.or(hover)?;
+        if self.warnings(board, color, pos).len() == 0 {
+            return None;
+        }
+        Some(pos)
+    }
+    /*
+        shownの手をオレンジの円で囲み，警告を盤面の上側に描画する
+    */
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        font: &graphics::Font,
+        board: &Board,
+        color: Stone,
+        hover: Option<(usize, usize)>,
+    ) -> GameResult<()> {
+        let pos = match self.shown(board, color, hover) {
+            Some(pos) => pos,
+            None => return Ok(()),
+        };
+        let warnings = self.warnings(board, color, pos);
+        let mut text = format!("コーチ: {}", Board::pos_to_notation(pos));
+        for warning in warnings.iter() {
+            text += &format!("\n{}", warning.message());
+        }
+        if self.pending == Some(pos) {
+            text += "\nもう一度クリックするとそれでも打ちます";
+        } else if self.receiver.is_some() {
+            text += "\n(勝率は探索中)";
+        }
+        let orange = graphics::Color::new(1.0, 0.6, 0.1, 1.0);
+        super::draw_banner(ctx, font, &text, orange)?;
+        let cell_size = crate::WIDTH / BOARDSIZE as f32;
+        let ring = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::stroke(4.0),
+            Vec2::new(0.0, 0.0),
+            cell_size / 2.3,
+            0.1,
+            orange,
+        )?;
+        let center = Vec2::new(
+            pos.1 as f32 * cell_size + cell_size / 2.0,
+            pos.0 as f32 * cell_size + cell_size / 2.0,
+        );
+        graphics::draw(ctx, &ring, (center,))
+    }
+}
+
+pub fn is_corner(pos: (usize, usize)) -> bool {
+    (pos.0 == 0 || pos.0 == BOARDSIZE - 1) && (pos.1 == 0 || pos.1 == BOARDSIZE - 1)
+}
@@ -0,0 +1,79 @@
+use crate::mygame::predict::{montecarlo, EngineConfig, Playout, PLAYOUT_GREEDY};
+
+/*
+    CPUの強さ(難易度)
+    難易度ごとに探索回数，プレイアウトの方法，わざと悪い手を打つ確率，完全読みの有無を決める
+    シードから対局を再現できるよう，時間で探索を打ち切る設定(time)は使わない(対戦ツールで指定する)
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Normal,
+    Hard,
+    Expert,
+}
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Beginner,
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+    //画面表示・設定ファイル・EngineConfig::parseのlevelで使う名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+            .copied()
+    }
+    //次の難易度(Expertの次はBeginnerに戻る)
+    pub fn next(&self) -> Difficulty {
+        let i = Difficulty::ALL.iter().position(|d| d == self).unwrap();
+        Difficulty::ALL[(i + 1) % Difficulty::ALL.len()]
+    }
+    pub fn config(&self) -> EngineConfig {
+        let mut config = EngineConfig::default();
+        match self {
+            Difficulty::Beginner => {
+                config.maxtry = 100;
+                config.book = false;
+                config.mistake = 0.3;
+            }
+            Difficulty::Easy => {
+                config.maxtry = 500;
+                config.mistake = 0.1;
+            }
+            Difficulty::Normal => {} //従来の強さ
+            Difficulty::Hard => {
+                config.playout = Playout::Heavy {
+                    greedy: PLAYOUT_GREEDY,
+                };
+                config.endgame = true;
+            }
+            Difficulty::Expert => {
+                config.maxtry = montecarlo::MAXTRY * 5;
+                config.playout = Playout::Heavy {
+                    greedy: PLAYOUT_GREEDY,
+                };
+                config.endgame = true;
+            }
+        }
+        config
+    }
+}
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
@@ -0,0 +1,79 @@
+use crate::mygame::record::StartPosition;
+use crate::mygame::{predict, Board, Stone, BOARDSIZE};
+use ggez::{graphics, Context, GameResult};
+
+/*
+    盤面編集
+    マスをクリックするたびに 空き→黒→白→空き の順に石を変え，手番を選んで好きな局面を作る
+    編集を終えるときに対局を続けられる局面かを確かめ，その局面を最初の局面として対局・解析を続ける
+*/
+pub struct Editor {
+    message: Option<String>, //編集を終えられなかった理由
+}
+impl Editor {
+    pub fn new() -> Editor {
+        Editor { message: None }
+    }
+    //posの石を次の状態にする
+    pub fn cycle(&mut self, board: &mut Board, pos: (usize, usize)) {
+        let next = match board.gamebord[pos.0][pos.1] {
+            Stone::Blank => Stone::Black,
+            Stone::Black => Stone::White,
+            Stone::White => Stone::Blank,
+        };
+        board.setstone(&vec![pos], next);
+        board.count_stone();
+        self.message = None;
+    }
+    //全てのマスを空きにする
+    pub fn clear(&mut self, board: &mut Board) {
+        let mut list = Vec::with_capacity(BOARDSIZE * BOARDSIZE);
+        for y in 0..BOARDSIZE {
+            for x in 0..BOARDSIZE {
+                list.push((y, x));
+            }
+        }
+        board.setstone(&list, Stone::Blank);
+        board.count_stone();
+        self.message = None;
+    }
+    /*
+        colorの手番で編集した盤面から対局を続けられるかを確かめ，最初の局面を返す
+        手番側に置ける場所がなく相手は置ける場合は，相手の手番から始める(パスを補う)
+        どちらも置けない場合はNoneを返し，理由を表示する
+    */
+    pub fn finish(&mut self, board: &Board, color: Stone) -> Option<StartPosition> {
+        let color = if predict::can_set_pos(board, color).len() != 0 {
+            color
+        } else if predict::can_set_pos(board, color.return_reverse_color()).len() != 0 {
+            color.return_reverse_color()
+        } else {
+            self.message = Some(
+                "どちらの手番も置ける場所がないため，この局面からは続けられません".to_string(),
+            );
+            return None;
+        };
+        let mut board = *board;
+        board.count_stone();
+        Some(StartPosition {
+            board: board,
+            color: color,
+        })
+    }
+    /*
+        盤面の上側に編集中であることと操作方法を描画する
+        編集を終えられなかった場合はその理由も描画する
+    */
+    pub fn draw(&self, ctx: &mut Context, font: &graphics::Font) -> GameResult<()> {
+        let mut text = "盤面編集: マスをクリックして 黒→白→空き".to_string();
+        if let Some(message) = &self.message {
+            text += &format!("\n{}", message);
+        }
+        let color = if self.message.is_some() {
+            graphics::Color::new(1.0, 0.5, 0.5, 1.0)
+        } else {
+            graphics::Color::WHITE
+        };
+        super::draw_banner(ctx, font, &text, color)
+    }
+}
@@ -0,0 +1,310 @@
+use crate::mygame::predict::{self, montecarlo::MoveStat, EngineConfig};
+use crate::mygame::record::{self, StartPosition};
+use crate::mygame::review::{self, ReviewedMove};
+use crate::mygame::{random, Board, Stone};
+use ggez::{graphics, Context, GameResult};
+use glam::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/*
+    対局後の評価値のグラフ
+    棋譜の各局面(初期局面と各手の後)を別スレッドで順に探索し，白(プレイヤー)から見た勝率を求める
+    求まった局面から順にグラフへ描画し，グラフをクリックするとその局面を盤面に表示する
+*/
+const GRAPH_MAXTRY: usize = 2000; //一つの局面の探索回数
+const SWING: f32 = 0.2; //前の局面から勝率がこれ以上変わった点に印をつける
+const GRAPH_LEFT: f32 = 20.0;
+const GRAPH_TOP: f32 = 270.0;
+const GRAPH_WIDTH: f32 = 440.0;
+const GRAPH_HEIGHT: f32 = 170.0;
+const GRAPH_FONT_SIZE: f32 = 14.0;
+
+#[derive(Clone)]
+pub struct PositionEval {
+    pub color: Stone,                 //手番(終局している場合はBlank)
+    pub white_win: f32,               //白から見た勝率(0〜1)
+    pub best: Option<(usize, usize)>, //探索で最善とした手
+    pub children: Vec<MoveStat>,      //候補手ごとの統計(振り返りで打った手と最善の手を比べる)
+}
+
+pub struct EvalGraph {
+    pub visible: bool,
+    pub view: Option<usize>, //盤面に表示している局面(何手目の後か)
+    start: Option<StartPosition>,
+    moves: Vec<(usize, usize)>,
+    evals: Arc<Mutex<Vec<PositionEval>>>,
+    stop: Arc<AtomicBool>,
+}
+impl EvalGraph {
+    pub fn new() -> EvalGraph {
+        EvalGraph {
+            visible: false,
+            view: None,
+            start: None,
+            moves: Vec::new(),
+            evals: Arc::new(Mutex::new(Vec::new())),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    /*
+        棋譜の各局面の探索を始める．探索中の前の棋譜があれば止める
+        startは対局の最初の局面(Noneは初期盤面)
+        seedは対局のシード(局面ごとにその手数から乱数列を作る)
+    */
+    pub fn start(&mut self, start: Option<StartPosition>, moves: &[(usize, usize)], seed: u64) {
+        self.stop();
+        self.start = start;
+        self.moves = moves.to_vec();
+        self.view = None;
+        let evals = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        self.evals = evals.clone();
+        self.stop = stop.clone();
+        let moves = self.moves.clone();
+        let mut config = EngineConfig::default();
+        config.maxtry = GRAPH_MAXTRY;
+        config.book = false;
+        config.endgame = true;
+        thread::spawn(move || {
+            for i in 0..=moves.len() {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                let (board, color) = match record::replay_from(start, &moves[..i], 0, 0) {
+                    Some(v) => v,
+                    None => return,
+                };
+                let mut random = random::from_seed(random::derive(seed, i as u64));
+                let eval = evaluate_position(&board, color, &config, &mut random);
+                evals.lock().unwrap().push(eval);
+            }
+        });
+    }
+    //探索中であれば止める
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+    //これまでに求まった局面の評価(初期局面から順)
+    pub fn evals(&self) -> Vec<PositionEval> {
+        self.evals.lock().unwrap().clone()
+    }
+    //これまでに求まった局面の評価から，colorの手を振り返る
+    pub fn review(&self, color: Stone) -> Vec<ReviewedMove> {
+        review::review(&self.moves, &self.evals(), color)
+    }
+    /*
+        表示中の局面より後で，緩手以上と判定された手の後の局面を表示する
+        最後まで進んだら最初の判定された手に戻る．判定された手がなければfalse
+    */
+    pub fn view_next_reviewed(&mut self, reviewed: &[ReviewedMove]) -> bool {
+        let tagged: Vec<usize> = reviewed
+            .iter()
+            .filter(|m| m.severity.is_some())
+            .map(|m| m.index + 1)
+            .collect();
+        let current = self.view.unwrap_or(0);
+        match tagged.iter().find(|&&i| i > current).or(tagged.get(0)) {
+            Some(&i) => {
+                self.view = Some(i);
+                true
+            }
+            None => false,
+        }
+    }
+    //盤面に表示する局面．表示していない場合はNone
+    pub fn view_board(&self, width: u32, height: u32) -> Option<Board> {
+        let view = self.view?;
+        record::replay_from(self.start, &self.moves[..view], width, height).map(|(board, _)| board)
+    }
+    /*
+        画面上の位置posがグラフ内であれば，その位置に対応する局面(何手目の後か)を返す
+    */
+    pub fn index_at(&self, pos: mint::Point2<f32>) -> Option<usize> {
+        if !self.visible
+            || pos.x < GRAPH_LEFT
+            || pos.x > GRAPH_LEFT + GRAPH_WIDTH
+            || pos.y < GRAPH_TOP
+            || pos.y > GRAPH_TOP + GRAPH_HEIGHT
+        {
+            return None;
+        }
+        let step = GRAPH_WIDTH / std::cmp::max(self.moves.len(), 1) as f32;
+        let index = ((pos.x - GRAPH_LEFT) / step).round() as usize;
+        Some(std::cmp::min(index, self.moves.len()))
+    }
+    fn point(&self, index: usize, white_win: f32) -> Vec2 {
+        let step = GRAPH_WIDTH / std::cmp::max(self.moves.len(), 1) as f32;
+        Vec2::new(
+            GRAPH_LEFT + index as f32 * step,
+            GRAPH_TOP + (1.0 - white_win) * GRAPH_HEIGHT,
+        )
+    }
+    /*
+        グラフを盤面の下側に重ねて描画する
+        上ほど白(プレイヤー)が有利．勝率が大きく動いた点は赤い点，表示中の局面は黄色の線で示す
+        reviewedのうち緩手以上と判定された手は，その手の後の点を判定の色の円で囲み，
+        グラフの下に振り返りの要約(表示中の局面が判定された手の後であればその手の内容)を書く
+    */
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        font: &graphics::Font,
+        reviewed: &[ReviewedMove],
+    ) -> GameResult<()> {
+        let evals = self.evals();
+        let rect = graphics::Rect {
+            x: GRAPH_LEFT - 10.0,
+            y: GRAPH_TOP - 20.0,
+            w: GRAPH_WIDTH + 20.0,
+            h: GRAPH_HEIGHT + 50.0,
+        };
+        let panel = graphics::Mesh::new_rounded_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            rect,
+            10.0,
+            graphics::Color::new(0.0, 0.0, 0.0, 0.75),
+        )?;
+        graphics::draw(ctx, &panel, (Vec2::new(0.0, 0.0),))?;
+        let half = graphics::Mesh::new_line(
+            ctx,
+            &[self.point(0, 0.5), self.point(self.moves.len(), 0.5)],
+            1.0,
+            graphics::Color::new(0.6, 0.6, 0.6, 1.0),
+        )?;
+        graphics::draw(ctx, &half, (Vec2::new(0.0, 0.0),))?;
+        let caption = format!(
+            "White(You)の勝率  {}/{}局面",
+            evals.len(),
+            self.moves.len() + 1
+        );
+        let text = graphics::Text::new((caption, *font, GRAPH_FONT_SIZE));
+        graphics::draw(
+            ctx,
+            &text,
+            (
+                Vec2::new(GRAPH_LEFT, GRAPH_TOP - 18.0),
+                graphics::Color::WHITE,
+            ),
+        )?;
+        if let Some(view) = self.view {
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[self.point(view, 1.0), self.point(view, 0.0)],
+                2.0,
+                graphics::Color::new(1.0, 0.9, 0.2, 1.0),
+            )?;
+            graphics::draw(ctx, &line, (Vec2::new(0.0, 0.0),))?;
+        }
+        let points: Vec<Vec2> = evals
+            .iter()
+            .enumerate()
+            .map(|(i, eval)| self.point(i, eval.white_win))
+            .collect();
+        if points.len() >= 2 {
+            let line = graphics::Mesh::new_line(ctx, &points, 2.0, graphics::Color::WHITE)?;
+            graphics::draw(ctx, &line, (Vec2::new(0.0, 0.0),))?;
+        }
+        for i in 1..evals.len() {
+            if (evals[i].white_win - evals[i - 1].white_win).abs() >= SWING {
+                let circle = graphics::Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    Vec2::new(0.0, 0.0),
+                    4.0,
+                    0.1,
+                    graphics::Color::new(1.0, 0.2, 0.2, 1.0),
+                )?;
+                graphics::draw(ctx, &circle, (points[i],))?;
+            }
+        }
+        for m in reviewed.iter() {
+            if let (Some(severity), Some(point)) = (m.severity, points.get(m.index + 1)) {
+                let ring = graphics::Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::stroke(2.0),
+                    Vec2::new(0.0, 0.0),
+                    7.0,
+                    0.1,
+                    severity.color(),
+                )?;
+                graphics::draw(ctx, &ring, (*point,))?;
+            }
+        }
+        let selected = self
+            .view
+            .and_then(|view| reviewed.iter().find(|m| m.index + 1 == view));
+        let text = match selected {
+            Some(m) => format!(
+                "{}手目 {} {} 勝率-{:.0}%{}",
+                m.index + 1,
+                Board::pos_to_notation(m.pos),
+                m.severity.map_or("", |s| s.name()),
+                m.loss * 100.0,
+                match m.best {
+                    Some(best) if best != m.pos =>
+                        format!(" 最善 {}", Board::pos_to_notation(best)),
+                    _ => String::new(),
+                }
+            ),
+            None => format!("You: {}", review::summary(reviewed)),
+        };
+        let text = graphics::Text::new((text, *font, GRAPH_FONT_SIZE));
+        graphics::draw(
+            ctx,
+            &text,
+            (
+                Vec2::new(GRAPH_LEFT, GRAPH_TOP + GRAPH_HEIGHT + 8.0),
+                graphics::Color::WHITE,
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+/*
+    一つの局面を探索し，白から見た勝率と最善手を求める
+    完全読みできた場合の勝率は勝ち1，引き分け0.5，負け0とする
+*/
+fn evaluate_position(
+    board: &Board,
+    color: Stone,
+    config: &EngineConfig,
+    random: &mut random::GameRng,
+) -> PositionEval {
+    if predict::can_set_pos(board, color).len() == 0 {
+        //record::replayはパスを補うので，手番側が置けないのは終局のみ
+        let white_win = if board.white_num > board.black_num {
+            1.0
+        } else if board.white_num < board.black_num {
+            0.0
+        } else {
+            0.5
+        };
+        return PositionEval {
+            color: Stone::Blank,
+            white_win: white_win,
+            best: None,
+            children: Vec::new(),
+        };
+    }
+    let stats = predict::search(board, color, config, random);
+    let best = if stats.children.iter().any(|m| m.score.is_some()) {
+        stats.children.iter().max_by_key(|m| m.score)
+    } else {
+        stats.children.iter().max_by_key(|m| m.visits)
+    };
+    let win = best.map_or(0.5, |m| m.win_rate);
+    PositionEval {
+        color: color,
+        white_win: if color as i32 == Stone::White as i32 {
+            win
+        } else {
+            1.0 - win
+        },
+        best: best.map(|m| m.pos),
+        children: stats.children.clone(),
+    }
+}
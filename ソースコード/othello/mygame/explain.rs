@@ -0,0 +1,146 @@
+use crate::mygame::coach::is_corner;
+use crate::mygame::predict::stats::{SearchMethod, SearchStats};
+use crate::mygame::predict::{self, montecarlo::MoveStat};
+use crate::mygame::{Board, Stone};
+use ggez::{graphics, Context, GameResult};
+
+/*
+    CPUの手の説明
+    CPUが打った手について，ひっくり返した石の数・両者の置ける場所の数の変化・角の取り合い・
+    探索で求めたその手と次善の手の評価を文章にする
+    次善の手は，モンテカルロ木探索では試行回数が二番目に多い手，完全読みでは石数差が二番目に良い手とする
+*/
+pub struct Explanation {
+    pos: (usize, usize),
+    flipped: usize,
+    mobility: ((usize, usize), (usize, usize)), //(CPU，相手)の置ける場所の数の(打つ前，打った後)
+    taken: bool,                                //角を取った
+    denied: Vec<(usize, usize)>,                //相手が取れた角で，取れなくなった角
+    given: Vec<(usize, usize)>,                 //相手が新しく取れるようになった角
+    method: Option<SearchMethod>,
+    chosen: Option<MoveStat>, //打った手の探索の統計
+    second: Option<MoveStat>, //次善の手の探索の統計
+}
+impl Explanation {
+    /*
+        colorがboardのposに打つ前に呼ぶ
+        statsはこの手を決めた思考の統計(外部エンジン等で得られない場合はNone)
+        posに置けない場合はNone
+    */
+    pub fn new(
+        board: &Board,
+        color: Stone,
+        pos: (usize, usize),
+        stats: Option<&SearchStats>,
+    ) -> Option<Explanation> {
+        let list = predict::can_cnt(&board.gamebord, color, pos, false);
+        if list.len() == 0 {
+            return None;
+        }
+        let opponent = color.return_reverse_color();
+        let mut after = *board;
+        predict::put_stone(&mut after, color, pos);
+        let before_moves = predict::can_set_pos(board, opponent);
+        let after_moves = predict::can_set_pos(&after, opponent);
+        let corners = |moves: &Vec<(usize, usize)>, other: &Vec<(usize, usize)>| {
+            moves
+                .iter()
+                .filter(|&&p| is_corner(p) && !other.contains(&p) && p != pos)
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let (chosen, second) = match stats {
+            Some(stats) => rank(stats, pos),
+            None => (None, None),
+        };
+        Some(Explanation {
+            pos: pos,
+            flipped: list.len().saturating_sub(1), //置いた石の分を除く
+            mobility: (
+                (
+                    predict::can_set_pos(board, color).len(),
+                    predict::can_set_pos(&after, color).len(),
+                ),
+                (before_moves.len(), after_moves.len()),
+            ),
+            taken: is_corner(pos),
+            denied: corners(&before_moves, &after_moves),
+            given: corners(&after_moves, &before_moves),
+            method: stats.map(|stats| stats.method),
+            chosen: chosen,
+            second: second,
+        })
+    }
+    pub fn text(&self) -> String {
+        let notation = |list: &Vec<(usize, usize)>| {
+            let list: Vec<String> = list.iter().map(|&p| Board::pos_to_notation(p)).collect();
+            list.join(" ")
+        };
+        let mut text = format!(
+            "CPU {}: {}個ひっくり返しました",
+            Board::pos_to_notation(self.pos),
+            self.flipped
+        );
+        let ((cpu_before, cpu_after), (you_before, you_after)) = self.mobility;
+        text += &format!(
+            "\n置ける場所 あなた {}→{}  CPU {}→{}",
+            you_before, you_after, cpu_before, cpu_after
+        );
+        if self.taken {
+            text += "\n角を取りました";
+        }
+        if self.denied.len() != 0 {
+            text += &format!("\nあなたが取れた角{}を防ぎました", notation(&self.denied));
+        }
+        if self.given.len() != 0 {
+            text += &format!("\nあなたに角{}を与えました", notation(&self.given));
+        }
+        match (self.method, &self.chosen) {
+            (Some(SearchMethod::Book), _) => text += "\n定石の手です",
+            (Some(SearchMethod::Random), _) => text += "\n難易度のためにわざと選んだ手です",
+            (_, Some(chosen)) => {
+                let value = |m: &MoveStat| match m.score {
+                    Some(score) => format!("石数差{:+}", score),
+                    None => format!("勝率{:.0}%({}回)", m.win_rate * 100.0, m.visits),
+                };
+                text += &format!("\n評価 {}", value(chosen));
+                if let Some(second) = &self.second {
+                    text += &format!(
+                        "  次善 {} {}",
+                        Board::pos_to_notation(second.pos),
+                        value(second)
+                    );
+                }
+            }
+            _ => {}
+        }
+        text
+    }
+    //説明を盤面の上側に描画する
+    pub fn draw(&self, ctx: &mut Context, font: &graphics::Font) -> GameResult<()> {
+        super::draw_banner(
+            ctx,
+            font,
+            &self.text(),
+            graphics::Color::new(0.6, 0.9, 1.0, 1.0),
+        )
+    }
+}
+
+/*
+    statsの候補手のうち，打った手posと次善の手の統計
+    石数差が求まっていれば石数差の順，それ以外は試行回数の順で次善を決める
+*/
+fn rank(stats: &SearchStats, pos: (usize, usize)) -> (Option<MoveStat>, Option<MoveStat>) {
+    let chosen = stats.children.iter().find(|m| m.pos == pos).cloned();
+    let second = stats
+        .children
+        .iter()
+        .filter(|m| m.pos != pos)
+        .max_by(|a, b| match (a.score, b.score) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => a.visits.cmp(&b.visits),
+        })
+        .cloned();
+    (chosen, second)
+}
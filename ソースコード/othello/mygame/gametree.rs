@@ -0,0 +1,271 @@
+use crate::mygame::record::{self, StartPosition};
+use crate::mygame::{Board, Stone};
+
+/*
+    変化を含む棋譜
+    各局面(ノード)は，その局面へ至った手と，その局面から打たれた手(子)を持つ．最初の子を本線とする
+    前の局面に戻って別の手を打つと，元の手順を残したまま新しい変化として子に加える
+    文字列にするときは本線の手を続けて書き，別の変化はその変化で代わりに打たれた手の後に括弧で囲んで書く
+        "f5d6c3(c5f4)d3" : f5d6の後の本線はc3d3で，c3の代わりにc5f4と打った変化がある
+    パスは書かない(record::replayと同じく自動で補う)
+    盤面編集で作った局面から始めた場合は，その局面をstartに持つ(文字列には含めない)
+*/
+#[derive(Clone, Debug)]
+struct Node {
+    pos: Option<(usize, usize)>, //この局面へ至った手(最初の局面はNone)
+    parent: Option<usize>,
+    children: Vec<usize>, //最初の子が本線
+}
+
+#[derive(Clone)]
+pub struct GameTree {
+    pub start: Option<StartPosition>, //最初の局面(Noneは初期盤面)
+    nodes: Vec<Node>,                 //削除した変化のノードも残る(どこからも辿れなくなるだけ)
+    current: usize,                   //盤面に表示している局面
+}
+impl GameTree {
+    pub fn new() -> GameTree {
+        GameTree {
+            start: None,
+            nodes: vec![Node {
+                pos: None,
+                parent: None,
+                children: Vec::new(),
+            }],
+            current: 0,
+        }
+    }
+    //一手も打たれていない
+    pub fn is_empty(&self) -> bool {
+        self.nodes[0].children.len() == 0
+    }
+    //最初の局面からmovesを打ち進めた盤面と次の手番
+    pub fn replay(
+        &self,
+        moves: &[(usize, usize)],
+        width: u32,
+        height: u32,
+    ) -> Option<(Board, Stone)> {
+        record::replay_from(self.start, moves, width, height)
+    }
+    //最初の局面から現在の局面までの手数
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut id = self.current;
+        while let Some(parent) = self.nodes[id].parent {
+            depth += 1;
+            id = parent;
+        }
+        depth
+    }
+    //最初の局面から現在の局面までの手
+    pub fn moves(&self) -> Vec<(usize, usize)> {
+        let mut ret = Vec::new();
+        let mut id = self.current;
+        while let Some(pos) = self.nodes[id].pos {
+            ret.push(pos);
+            id = self.nodes[id].parent.unwrap();
+        }
+        ret.reverse();
+        ret
+    }
+    //最初の局面からの本線の手
+    pub fn main_line(&self) -> Vec<(usize, usize)> {
+        let mut ret = Vec::new();
+        let mut id = 0;
+        while let Some(&child) = self.nodes[id].children.get(0) {
+            ret.push(self.nodes[child].pos.unwrap());
+            id = child;
+        }
+        ret
+    }
+    //現在の局面から打たれた手(本線が最初)
+    pub fn next_moves(&self) -> Vec<(usize, usize)> {
+        self.nodes[self.current]
+            .children
+            .iter()
+            .map(|&child| self.nodes[child].pos.unwrap())
+            .collect()
+    }
+    pub fn has_next(&self) -> bool {
+        self.nodes[self.current].children.len() != 0
+    }
+    /*
+        現在の局面でposを打ち，打った後の局面へ進む
+        既に打たれた手であればその変化へ進むだけで，新しい変化を作った場合はtrue
+    */
+    pub fn play(&mut self, pos: (usize, usize)) -> bool {
+        let children = &self.nodes[self.current].children;
+        if let Some(&child) = children.iter().find(|&&c| self.nodes[c].pos == Some(pos)) {
+            self.current = child;
+            return false;
+        }
+        self.nodes.push(Node {
+            pos: Some(pos),
+            parent: Some(self.current),
+            children: Vec::new(),
+        });
+        let id = self.nodes.len() - 1;
+        self.nodes[self.current].children.push(id);
+        self.current = id;
+        true
+    }
+    //一手前の局面へ戻る．最初の局面の場合はfalse
+    pub fn back(&mut self) -> bool {
+        match self.nodes[self.current].parent {
+            Some(parent) => {
+                self.current = parent;
+                true
+            }
+            None => false,
+        }
+    }
+    //本線の次の局面へ進む．次の手がない場合はfalse
+    pub fn forward(&mut self) -> bool {
+        match self.nodes[self.current].children.get(0) {
+            Some(&child) => {
+                self.current = child;
+                true
+            }
+            None => false,
+        }
+    }
+    //本線の最後の局面へ進む
+    pub fn forward_to_end(&mut self) {
+        while self.forward() {}
+    }
+    /*
+        現在の局面へ至った手の代わりに打たれた別の変化へ移る(deltaは変化の順番をいくつ進めるか)
+        別の変化がない場合はfalse
+    */
+    pub fn switch_variation(&mut self, delta: i32) -> bool {
+        let parent = match self.nodes[self.current].parent {
+            Some(parent) => parent,
+            None => return false,
+        };
+        let siblings = &self.nodes[parent].children;
+        if siblings.len() < 2 {
+            return false;
+        }
+        let index = siblings.iter().position(|&c| c == self.current).unwrap() as i32;
+        let index = (index + delta).rem_euclid(siblings.len() as i32) as usize;
+        self.current = siblings[index];
+        true
+    }
+    /*
+        現在の局面へ至った手が，一手前の局面から打たれた手のうち何番目の変化か(0が本線)と変化の数
+        最初の局面は(0,1)
+    */
+    pub fn variation(&self) -> (usize, usize) {
+        match self.nodes[self.current].parent {
+            Some(parent) => {
+                let siblings = &self.nodes[parent].children;
+                let index = siblings.iter().position(|&c| c == self.current).unwrap();
+                (index, siblings.len())
+            }
+            None => (0, 1),
+        }
+    }
+    //最初の局面から現在の局面までの手順が本線上にあるか
+    pub fn is_main_line(&self) -> bool {
+        let mut id = self.current;
+        while let Some(parent) = self.nodes[id].parent {
+            if self.nodes[parent].children[0] != id {
+                return false;
+            }
+            id = parent;
+        }
+        true
+    }
+    /*
+        最初の局面から現在の局面までの手順を本線にする
+        元の本線は各局面の二番目の変化になる．既に本線の場合はfalse
+    */
+    pub fn promote(&mut self) -> bool {
+        let mut changed = false;
+        let mut id = self.current;
+        while let Some(parent) = self.nodes[id].parent {
+            let children = &mut self.nodes[parent].children;
+            let index = children.iter().position(|&c| c == id).unwrap();
+            if index != 0 {
+                children.remove(index);
+                children.insert(0, id);
+                changed = true;
+            }
+            id = parent;
+        }
+        changed
+    }
+    /*
+        現在の局面へ至った手とその後の手順をすべて削除し，一手前の局面へ戻る
+        最初の局面の場合はfalse
+    */
+    pub fn delete(&mut self) -> bool {
+        let parent = match self.nodes[self.current].parent {
+            Some(parent) => parent,
+            None => return false,
+        };
+        let current = self.current;
+        self.nodes[parent].children.retain(|&c| c != current);
+        self.current = parent;
+        true
+    }
+    //記録用の文字列(冒頭のコメントの形式)
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.write_node(0, &mut text);
+        text
+    }
+    fn write_node(&self, id: usize, text: &mut String) {
+        let children = &self.nodes[id].children;
+        let main = match children.get(0) {
+            Some(&main) => main,
+            None => return,
+        };
+        *text += &Board::pos_to_notation(self.nodes[main].pos.unwrap());
+        for &child in children[1..].iter() {
+            *text += "(";
+            *text += &Board::pos_to_notation(self.nodes[child].pos.unwrap());
+            self.write_node(child, text);
+            *text += ")";
+        }
+        self.write_node(main, text);
+    }
+    /*
+        to_textの形式の文字列を読み込む．現在の局面は最初の局面になる
+        手が置けるかは確かめない(record::replayで確かめる)
+    */
+    pub fn parse(text: &str) -> Option<GameTree> {
+        let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if !text.is_ascii() {
+            return None;
+        }
+        let mut tree = GameTree::new();
+        let mut stack = Vec::new(); //変化の括弧を閉じた後に戻る局面
+        let mut i = 0;
+        while i < text.len() {
+            match &text[i..i + 1] {
+                "(" => {
+                    //直前の手の代わりの変化なので，直前の手を打つ前の局面から始める
+                    stack.push(tree.current);
+                    tree.current = tree.nodes[tree.current].parent?;
+                    i += 1;
+                }
+                ")" => {
+                    tree.current = stack.pop()?;
+                    i += 1;
+                }
+                _ => {
+                    let pos = Board::notation_to_pos(text.get(i..i + 2)?)?;
+                    tree.play(pos);
+                    i += 2;
+                }
+            }
+        }
+        if stack.len() != 0 {
+            return None;
+        }
+        tree.current = 0;
+        Some(tree)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::mygame::predict::{self, EngineConfig};
+use crate::mygame::{random, Board, Stone};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/*
+    ヒント
+    プレイヤー側の手番の局面をCPUと同じ探索で別スレッドで考え，おすすめの位置を返す
+    局面が変わったら(プレイヤーが石を置いたら)ヒントは消す
+*/
+pub struct Hint {
+    key: Option<u64>, //ヒントを求めた局面(Board::hash)
+    receiver: Option<Receiver<Option<(usize, usize)>>>,
+    pos: Option<(usize, usize)>,
+    pub count: u32, //この対局でヒントを使った回数
+}
+impl Hint {
+    pub fn new() -> Hint {
+        Hint {
+            key: None,
+            receiver: None,
+            pos: None,
+            count: 0,
+        }
+    }
+    /*
+        colorの手番のboardについてヒントを求め始める
+        既に同じ局面のヒントを表示中，もしくは考え中の場合はfalse
+        seedは対局のシードとその時点の手数から作ったもの
+    */
+    pub fn request(&mut self, board: &Board, color: Stone, seed: u64) -> bool {
+        let key = board.hash(color);
+        if self.key == Some(key) {
+            return false;
+        }
+        self.key = Some(key);
+        self.pos = None;
+        self.count += 1;
+        let board = *board;
+        let mut config = EngineConfig::default();
+        config.endgame = true;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let pos = predict::best_move(&board, color, &config, &mut random::from_seed(seed));
+            let _ = sender.send(pos);
+        });
+        self.receiver = Some(receiver);
+        true
+    }
+    /*
+        毎フレーム呼ぶ．探索が終わっていれば結果を受け取り，局面が変わっていればヒントを消す
+    */
+    pub fn update(&mut self, board: &Board, color: Stone) {
+        if self.key.is_some() && self.key != Some(board.hash(color)) {
+            self.clear();
+            return;
+        }
+        if let Some(receiver) = &self.receiver {
+            if let Ok(pos) = receiver.try_recv() {
+                self.pos = pos;
+                self.receiver = None;
+            }
+        }
+    }
+    pub fn thinking(&self) -> bool {
+        self.receiver.is_some()
+    }
+    //表示するヒントの位置
+    pub fn pos(&self) -> Option<(usize, usize)> {
+        self.pos
+    }
+    //表示中・考え中のヒントを消す(考え中の探索の結果は捨てる)
+    pub fn clear(&mut self) {
+        self.key = None;
+        self.receiver = None;
+        self.pos = None;
+    }
+}
@@ -0,0 +1,102 @@
+use crate::mygame::{predict, Board, Stone, BOARDSIZE};
+use ggez::{graphics, Context, GameResult};
+use glam::*;
+
+/*
+    置けないマスをクリックしたときの表示
+    クリックしたマスを赤く光らせて揺らし，置けない理由を盤面の上側に表示する
+*/
+const FLASH_TIME: f32 = 0.5; //マスを光らせる秒数
+const MESSAGE_TIME: f32 = 2.5; //理由を表示する秒数
+const SHAKE_WIDTH: f32 = 6.0; //揺れの最大の幅(ピクセル)
+const SHAKE_SPEED: f32 = 50.0; //揺れの速さ(ラジアン/秒)
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Reason {
+    Occupied, //既に石がある
+    NoFlip,   //ひっくり返せる石がない
+    MustPass, //どこにも置けないのでパスが必要
+}
+impl Reason {
+    /*
+        colorがposに置けない理由．置ける場合はNone
+        どこにも置けない場合は，クリックしたマスに関わらずパスが必要であることを理由とする
+    */
+    pub fn of(board: &Board, color: Stone, pos: (usize, usize)) -> Option<Reason> {
+        if predict::can_cnt(&board.gamebord, color, pos, false).len() != 0 {
+            None
+        } else if predict::can_set_pos(board, color).len() == 0 {
+            Some(Reason::MustPass)
+        } else if board.gamebord[pos.0][pos.1] as i32 != Stone::Blank as i32 {
+            Some(Reason::Occupied)
+        } else {
+            Some(Reason::NoFlip)
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match self {
+            Reason::Occupied => "そのマスには既に石があります",
+            Reason::NoFlip => "そこに置いてもひっくり返せる石がありません",
+            Reason::MustPass => "置ける場所がありません．passボタンを押してください",
+        }
+    }
+}
+
+pub struct IllegalClick {
+    pub pos: (usize, usize),
+    pub reason: Reason,
+    elapsed: f32, //クリックしてからの秒数
+}
+impl IllegalClick {
+    pub fn new(pos: (usize, usize), reason: Reason) -> IllegalClick {
+        IllegalClick {
+            pos: pos,
+            reason: reason,
+            elapsed: 0.0,
+        }
+    }
+    //時間を進める．表示を終えたらfalse
+    pub fn update(&mut self, delta: f32) -> bool {
+        self.elapsed += delta;
+        self.elapsed < MESSAGE_TIME
+    }
+    //クリックしたマスの赤い点滅と揺れ，置けない理由を描画する
+    pub fn draw(&self, ctx: &mut Context, font: &graphics::Font) -> GameResult<()> {
+        if self.elapsed < FLASH_TIME {
+            //時間とともに弱まりながら左右に揺れる
+            let rest = 1.0 - self.elapsed / FLASH_TIME;
+            let shake = (self.elapsed * SHAKE_SPEED).sin() * SHAKE_WIDTH * rest;
+            let cell_size = crate::WIDTH / BOARDSIZE as f32;
+            let rect = graphics::Rect {
+                x: 0.0,
+                y: 0.0,
+                w: cell_size,
+                h: cell_size,
+            };
+            let fill = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                rect,
+                graphics::Color::new(1.0, 0.1, 0.1, 0.5 * rest),
+            )?;
+            let frame = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(3.0),
+                rect,
+                graphics::Color::new(1.0, 0.2, 0.2, rest),
+            )?;
+            let corner = Vec2::new(
+                self.pos.1 as f32 * cell_size + shake,
+                self.pos.0 as f32 * cell_size,
+            );
+            graphics::draw(ctx, &fill, (corner,))?;
+            graphics::draw(ctx, &frame, (corner,))?;
+        }
+        super::draw_banner(
+            ctx,
+            font,
+            self.reason.message(),
+            graphics::Color::new(1.0, 0.5, 0.5, 1.0),
+        )
+    }
+}
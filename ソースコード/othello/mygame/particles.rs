@@ -1,3 +1,4 @@
+use super::random::{self, GameRng};
 use ggez::{graphics, Context, GameResult};
 use glam::*;
 use mint::Point2;
@@ -7,16 +8,19 @@ use std::collections::VecDeque;
     パーティクルの集合を管理
     パーティクルの削除を効率良く行うためにキューを2つもち，出し入れする
     LinkedListが理想だが，要素の削除がunsafeなため見送り
+    乱数は対局のシードから作ったものを使う
 */
 pub struct Particles {
     queue1: VecDeque<Particle>,
     queue2: VecDeque<Particle>,
+    random: GameRng,
 }
 impl Particles {
-    pub fn new() -> Particles {
+    pub fn new(seed: u64) -> Particles {
         let it = Particles {
             queue1: VecDeque::new(),
             queue2: VecDeque::new(),
+            random: random::from_seed(seed),
         };
         it
     }
@@ -94,19 +98,23 @@ impl Particles {
         };
         Ok(())
     }
-    fn add_particle(&mut self, it: Particle) {
-        //値が存在するキューへ追加する
-        if self.queue1.len() != 0 {
-            self.queue1.push_back(it);
+    fn add_particle(
+        queue1: &mut VecDeque<Particle>,
+        queue2: &mut VecDeque<Particle>,
+        it: Particle,
+    ) {
+        //値が存在するキューへ追加する(乱数を借りたまま呼べるよう，キューを直接受け取る)
+        if queue1.len() != 0 {
+            queue1.push_back(it);
         } else {
-            self.queue2.push_back(it);
+            queue2.push_back(it);
         };
     }
     /*
         石が置かれたorひっくり返った時に発生するパーティクルを生成
     */
     pub fn create_stone_particle(&mut self, pos: Point2<f32>, num: usize, color: &super::Stone) {
-        let mut random = rand::thread_rng();
+        let random = &mut self.random;
         for _ in 0..num {
             let color = match color {
                 super::Stone::Black => 0.0,
@@ -135,7 +143,7 @@ impl Particles {
                 acceleration,
                 color,
             );
-            self.add_particle(it);
+            Particles::add_particle(&mut self.queue1, &mut self.queue2, it);
         }
     }
     /*
@@ -146,7 +154,7 @@ impl Particles {
         let color_range = 0.5;
         let count = 2000;
         let y_pos = 100.0;
-        let mut random = rand::thread_rng();
+        let random = &mut self.random;
         for i in 0..count {
             let position = if i % 2 == 0 {
                 Point2 { x: 0.0, y: y_pos }
@@ -180,7 +188,7 @@ impl Particles {
                 },
                 color,
             );
-            self.add_particle(particle);
+            Particles::add_particle(&mut self.queue1, &mut self.queue2, particle);
         }
     }
 }
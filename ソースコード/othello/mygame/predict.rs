@@ -1,11 +1,365 @@
+use crate::mygame::random::GameRng;
 use crate::mygame::{board::Board, Stone, *};
 use rand::Rng;
+use std::time::Duration;
+pub mod book;
+pub mod endgame;
+pub mod evaluate;
+pub mod external;
 pub mod montecarlo;
+pub mod pattern;
+pub mod stats;
+pub mod transposition;
 
 /*
-    与えられた石のターンから，お互いランダムに行動を繰り返していく
+    CPU側の思考に使うエンジン
 */
-fn randommove(board: &mut Board, stonecolor: Stone) {
+pub enum Engine {
+    MonteCarlo(EngineConfig),           //組み込みのモンテカルロ木探索
+    External(external::ExternalPlayer), //子プロセスとして起動した外部エンジン
+}
+impl Engine {
+    pub fn think(
+        &mut self,
+        board: &Board,
+        color: Stone,
+        status: &detailedstatus::Status,
+        seed: u64, //この手の思考に使う乱数のシード(random::derive(対局のシード,手数))
+    ) -> Option<Option<(usize, usize)>> {
+        match self {
+            Engine::MonteCarlo(config) => {
+                montecarlo::montecarlotree(board, color, status, config, seed)
+            }
+            Engine::External(player) => player.think(board, color, status, seed),
+        }
+    }
+    /*
+        思考中の探索の途中経過
+        組み込みのモンテカルロ木探索で思考中の場合のみ得られる
+    */
+    pub fn progress(&self) -> Option<montecarlo::SearchProgress> {
+        match self {
+            Engine::MonteCarlo(_) => montecarlo::progress(),
+            Engine::External(_) => None,
+        }
+    }
+    /*
+        最後に終わった思考の統計
+        外部エンジンの場合はエンジンが出力したinfo行から作る(出力しない場合はNone)
+    */
+    pub fn last_stats(&self) -> Option<stats::SearchStats> {
+        match self {
+            Engine::MonteCarlo(_) => montecarlo::last_stats(),
+            Engine::External(player) => player.last_stats(),
+        }
+    }
+}
+
+/*
+    組み込みのモンテカルロ木探索の設定
+    "maxtry=2000,c=1.4,playout=heavy,greedy=0.1,book=off,time=1000,mistake=0.2,endgame=on"のような
+    文字列からも作成できる(対戦ツール等で使用)．"level=hard"で難易度(difficulty.rs)の設定を使う
+    levelは他の項目を書いた位置に関わらず基準とし，他の項目はその上に適用する("level=hard,maxtry=100"と同じ)
+*/
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub maxtry: usize,                //一回の思考での探索回数
+    pub time_limit: Option<Duration>, //一回の思考の時間の上限(探索回数に達していなくても打ち切る)
+    pub exploration: f32,             //UCBの探索項の係数
+    pub playout: Playout,             //プレイアウトでの着手の選び方
+    pub book: bool,                   //定石にある局面では探索せずに定石から選ぶ
+    pub mistake: f32,                 //わざと合法手からランダムに選ぶ確率(弱い難易度用)
+    pub endgame: bool,                //空きマスが少ない局面では完全読みを行う
+}
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            maxtry: montecarlo::MAXTRY,
+            time_limit: None,
+            exploration: std::f32::consts::PI,
+            playout: Playout::Uniform,
+            book: true,
+            mistake: 0.0,
+            endgame: false,
+        }
+    }
+}
+impl EngineConfig {
+    pub fn parse(spec: &str) -> Result<EngineConfig, String> {
+        let items: Vec<(&str, &str)> = spec
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|item| {
+                let mut kv = item.splitn(2, '=');
+                (kv.next().unwrap(), kv.next().unwrap_or(""))
+            })
+            .collect();
+        let mut config = EngineConfig::default();
+        for &(_, value) in items.iter().filter(|(key, _)| *key == "level") {
+            config = difficulty::Difficulty::from_name(value)
+                .ok_or_else(|| format!("levelの値が不正です: {}", value))?
+                .config();
+        }
+        for &(key, value) in items.iter() {
+            match key {
+                "maxtry" => {
+                    //一度も試行しないと置く位置が決まらないので，0は不正とする
+                    config.maxtry = match value.parse() {
+                        Ok(maxtry) if maxtry > 0 => maxtry,
+                        _ => return Err(format!("maxtryの値が不正です: {}", value)),
+                    }
+                }
+                "c" => {
+                    config.exploration = value
+                        .parse()
+                        .map_err(|_| format!("cの値が不正です: {}", value))?
+                }
+                "playout" => {
+                    config.playout = match (value, config.playout) {
+                        ("uniform", _) => Playout::Uniform,
+                        ("heavy", Playout::Heavy { greedy }) => Playout::Heavy { greedy: greedy },
+                        ("heavy", _) => Playout::Heavy {
+                            greedy: PLAYOUT_GREEDY,
+                        },
+                        _ => return Err(format!("playoutの値が不正です: {}", value)),
+                    }
+                }
+                "book" => config.book = parse_switch(key, value)?,
+                "endgame" => config.endgame = parse_switch(key, value)?,
+                "time" => {
+                    config.time_limit = Some(Duration::from_millis(
+                        value
+                            .parse()
+                            .map_err(|_| format!("timeの値が不正です: {}", value))?,
+                    ))
+                }
+                "mistake" => {
+                    config.mistake = value
+                        .parse()
+                        .map_err(|_| format!("mistakeの値が不正です: {}", value))?
+                }
+                "level" => {} //最初に適用済み
+                "greedy" => {
+                    config.playout = Playout::Heavy {
+                        greedy: value
+                            .parse()
+                            .map_err(|_| format!("greedyの値が不正です: {}", value))?,
+                    }
+                }
+                _ => return Err(format!("不明な設定項目です: {}", key)),
+            }
+        }
+        Ok(config)
+    }
+}
+fn parse_switch(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("{}の値が不正です: {}", key, value)),
+    }
+}
+
+/*
+    configに従って置く位置を決める．探索は呼び出し元のスレッドで行う
+    config.mistakeの確率でランダムな手を打ち，それ以外は
+    定石 -> 完全読み(config.endgameかつ空きマスがSOLVE_EMPTIES以下) -> モンテカルロ木探索
+    の順に使えるものから選ぶ
+    乱数は全てrandomから取る
+    None:置ける場所なし
+*/
+pub fn best_move(
+    board: &Board,
+    color: Stone,
+    config: &EngineConfig,
+    random: &mut GameRng,
+) -> Option<(usize, usize)> {
+    search(board, color, config, random).best
+}
+/*
+    best_moveと同じ方法で置く位置を決め，その思考の統計を返す
+*/
+pub fn search(
+    board: &Board,
+    color: Stone,
+    config: &EngineConfig,
+    random: &mut GameRng,
+) -> stats::SearchStats {
+    search_with_report(board, color, config, random, &mut |_| true)
+}
+/*
+    searchと同じ．モンテカルロ木探索を行う場合は途中経過をreportへ知らせる
+    (montecarlo::search_with_report)
+    それ以外の方法で決めた場合も，最後に一度だけ結果をreportへ知らせる
+*/
+pub fn search_with_report(
+    board: &Board,
+    color: Stone,
+    config: &EngineConfig,
+    random: &mut GameRng,
+    report: &mut dyn FnMut(&stats::SearchStats) -> bool,
+) -> stats::SearchStats {
+    let list = can_set_pos(board, color);
+    if list.len() == 0 {
+        return stats::SearchStats::new(stats::SearchMethod::MonteCarlo);
+    }
+    if config.mistake > 0.0 && random.gen_range(0.0, 1.0) < config.mistake {
+        let mut result = stats::SearchStats::new(stats::SearchMethod::Random);
+        result.best = Some(list[random.gen_range(0, list.len())]);
+        result.pv = result.best.into_iter().collect();
+        report(&result);
+        return result;
+    }
+    if config.book {
+        if let Some(pos) = book::shared().and_then(|book| book.choose(board, color, random)) {
+            let mut result = stats::SearchStats::new(stats::SearchMethod::Book);
+            result.best = Some(pos);
+            result.pv = vec![pos];
+            report(&result);
+            return result;
+        }
+    }
+    let empties = BOARDSIZE * BOARDSIZE - (board.black_num + board.white_num) as usize;
+    if config.endgame && empties <= endgame::SOLVE_EMPTIES {
+        let mut table = transposition::TranspositionTable::new(transposition::TABLE_BITS);
+        let result = endgame::search(board, color, &mut table);
+        report(&result);
+        return result;
+    }
+    montecarlo::search_with_report(board, color, config, random, report)
+}
+
+/*
+    colorの石をposに置き，ひっくり返して石数を更新する
+    置けない場所だった場合は何もせずfalseを返す
+*/
+pub fn put_stone(board: &mut Board, color: Stone, pos: (usize, usize)) -> bool {
+    let list = can_cnt(&board.gamebord, color, pos, false);
+    if list.len() == 0 {
+        return false;
+    }
+    board.setstone(&list, color);
+    board.count_stone();
+    true
+}
+
+/*
+    プレイアウトでの着手の選び方
+*/
+#[derive(Clone, Copy, Debug)]
+pub enum Playout {
+    Uniform, //合法手から一様ランダムに選ぶ
+    /*
+        隅を好み，隅の隣(X,C)やひっくり返す石の多い手を避けるよう重み付けしてランダムに選ぶ
+        greedyの確率で，代わりに評価値が最大となる手を選ぶ
+        評価値は学習済みのパターン評価(pattern.rs)があればそれを，なければ評価関数(evaluate.rs)を使う
+    */
+    Heavy { greedy: f32 },
+}
+pub const PLAYOUT_GREEDY: f32 = 0.1; //playout=heavyのみ指定した場合のgreedy
+const PLAYOUT_CORNER_WEIGHT: f32 = 20.0; //隅
+const PLAYOUT_X_WEIGHT: f32 = 0.1; //空いた隅の斜め隣
+const PLAYOUT_C_WEIGHT: f32 = 0.4; //空いた隅の縦横隣
+
+/*
+    heavyプレイアウトで手を評価する評価関数
+    重みの用意に時間がかかるので，探索一回につき一度だけ作ってプレイアウトへ渡す
+*/
+pub enum PlayoutEval {
+    Pattern(&'static pattern::PatternEval), //学習済みのパターン評価
+    Weights(evaluate::EvalWeights),         //学習済みの重みがない場合の評価関数
+}
+impl PlayoutEval {
+    pub fn new() -> PlayoutEval {
+        match pattern::shared() {
+            Some(eval) => PlayoutEval::Pattern(eval),
+            None => PlayoutEval::Weights(evaluate::EvalWeights::default()),
+        }
+    }
+    //colorから見た盤面の評価値
+    fn evaluate(&self, board: &Board, color: Stone) -> f32 {
+        match self {
+            PlayoutEval::Pattern(eval) => eval.evaluate(board, color),
+            PlayoutEval::Weights(weights) => evaluate::evaluate(board, color, weights),
+        }
+    }
+}
+
+/*
+    プレイアウトでの着手をlistの中から選ぶ
+*/
+fn choose_playout_move(
+    board: &Board,
+    color: Stone,
+    list: &Vec<(usize, usize)>,
+    playout: &Playout,
+    eval: &PlayoutEval,
+    random: &mut GameRng,
+) -> (usize, usize) {
+    let greedy = match playout {
+        Playout::Uniform => return list[random.gen_range(0, list.len())],
+        Playout::Heavy { greedy } => *greedy,
+    };
+    if random.gen_range(0.0, 1.0) < greedy {
+        let mut best = (list[0], std::f32::MIN);
+        for pos in list {
+            let mut next = *board;
+            put_stone(&mut next, color, *pos);
+            let score = eval.evaluate(&next, color);
+            if score > best.1 {
+                best = (*pos, score);
+            }
+        }
+        return best.0;
+    }
+    let n = BOARDSIZE - 1;
+    let weights: Vec<f32> = list
+        .iter()
+        .map(|&(y, x)| {
+            //隅に近い側の隅を求め，隅・X・Cのどれに当たるかを調べる
+            let corner = (
+                if y < BOARDSIZE / 2 { 0 } else { n },
+                if x < BOARDSIZE / 2 { 0 } else { n },
+            );
+            let (dy, dx) = (
+                (y as i32 - corner.0 as i32).abs(),
+                (x as i32 - corner.1 as i32).abs(),
+            );
+            let corner_empty = board.gamebord[corner.0][corner.1] as i32 == Stone::Blank as i32;
+            let square = if dy == 0 && dx == 0 {
+                PLAYOUT_CORNER_WEIGHT
+            } else if corner_empty && dy == 1 && dx == 1 {
+                PLAYOUT_X_WEIGHT
+            } else if corner_empty && dy + dx == 1 {
+                PLAYOUT_C_WEIGHT
+            } else {
+                1.0
+            };
+            //can_cntの返り値には置いた石も含まれる
+            let flips = can_cnt(&board.gamebord, color, (y, x), false).len() - 1;
+            square / flips as f32
+        })
+        .collect();
+    let mut r = random.gen_range(0.0, weights.iter().sum::<f32>());
+    for (pos, w) in list.iter().zip(weights.iter()) {
+        if r < *w {
+            return *pos;
+        }
+        r -= w;
+    }
+    list[list.len() - 1]
+}
+
+/*
+    与えられた石のターンから，お互いplayoutに従って行動を繰り返していく
+*/
+fn randommove(
+    board: &mut Board,
+    stonecolor: Stone,
+    playout: &Playout,
+    eval: &PlayoutEval,
+    random: &mut GameRng,
+) {
     let mut color = stonecolor;
     let mut cnt = 0;
     while board.black_num + board.white_num < (BOARDSIZE * BOARDSIZE) as u32 {
@@ -15,7 +369,7 @@ fn randommove(board: &mut Board, stonecolor: Stone) {
                 &can_cnt(
                     &board.gamebord,
                     color,
-                    list[rand::thread_rng().gen_range(0, list.len())],
+                    choose_playout_move(board, color, &list, playout, eval, random),
                     false,
                 ),
                 color,
@@ -109,3 +463,50 @@ pub fn can_cnt(
     }
     ret
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys() {
+        let config = EngineConfig::parse("maxtry=100,c=1.5,book=off,endgame=on").unwrap();
+        assert_eq!(config.maxtry, 100);
+        assert_eq!(config.exploration, 1.5);
+        assert!(!config.book);
+        assert!(config.endgame);
+        match EngineConfig::parse("playout=heavy,greedy=0.3")
+            .unwrap()
+            .playout
+        {
+            Playout::Heavy { greedy } => assert_eq!(greedy, 0.3),
+            Playout::Uniform => panic!("playout=heavyが反映されていません"),
+        }
+    }
+    #[test]
+    fn level_is_base_wherever_written() {
+        //levelより前に書いた項目も残る
+        for spec in ["maxtry=100,level=hard", "level=hard,maxtry=100"].iter() {
+            let config = EngineConfig::parse(spec).unwrap();
+            assert_eq!(config.maxtry, 100, "{}", spec);
+            assert!(config.endgame, "{}", spec);
+            if let Playout::Uniform = config.playout {
+                panic!("{}: levelのplayoutが反映されていません", spec);
+            }
+        }
+    }
+    #[test]
+    fn invalid_values_are_rejected() {
+        for spec in [
+            "maxtry=0",
+            "maxtry=x",
+            "level=impossible",
+            "book=yes",
+            "foo=1",
+        ]
+        .iter()
+        {
+            assert!(EngineConfig::parse(spec).is_err(), "{}", spec);
+        }
+    }
+}
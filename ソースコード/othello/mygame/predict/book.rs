@@ -0,0 +1,250 @@
+use crate::mygame::random::GameRng;
+use crate::mygame::{record, Board, Stone, BOARDSIZE};
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/*
+    定石(序盤の着手)の本
+    局面は手番側から見た形にし，8つの対称形(回転・反転)のうち最小のものに正規化して引く
+    ファイル形式(1行に1局面)
+        <正規化した盤面64文字(X:手番側 O:相手 -:空き)> <手>:<重み>:<平均石数差> ...
+    手は正規化した盤面上の座標，平均石数差は手番側から見た値
+*/
+pub const BOOK_FILE: &str = "./resources/book.txt";
+
+#[derive(Clone, Debug)]
+pub struct BookMove {
+    pub pos: (usize, usize), //正規化した盤面上の座標
+    pub weight: u32,         //選ばれやすさ(棋譜に現れた回数)
+    pub score: f32,          //その手を打った後の平均石数差
+}
+
+pub struct Book {
+    entries: HashMap<(u64, u64), Vec<BookMove>>,
+}
+impl Book {
+    pub fn new() -> Book {
+        Book {
+            entries: HashMap::new(),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /*
+        盤面を手番側から見た形の8つの対称形のうち，(手番側,相手)のビット列が最小となるものと，
+        その対称変換の番号を返す
+    */
+    fn normalize(board: &Board, color: Stone) -> ((u64, u64), usize) {
+        let mut best = ((std::u64::MAX, std::u64::MAX), 0);
+        for symmetry in 0..8 {
+            let (mut own, mut opp) = (0u64, 0u64);
+            for y in 0..BOARDSIZE {
+                for x in 0..BOARDSIZE {
+                    let stone = board.gamebord[y][x];
+                    if stone as i32 == Stone::Blank as i32 {
+                        continue;
+                    }
+                    let (ty, tx) = super::pattern::transform((y, x), symmetry);
+                    let bit = 1u64 << (ty * BOARDSIZE + tx);
+                    if stone as i32 == color as i32 {
+                        own |= bit;
+                    } else {
+                        opp |= bit;
+                    }
+                }
+            }
+            if (own, opp) < best.0 {
+                best = ((own, opp), symmetry);
+            }
+        }
+        best
+    }
+    /*
+        棋譜の一手を登録する
+        result:その手を打った側から見た最終的な石数差
+    */
+    pub fn add(&mut self, board: &Board, color: Stone, pos: (usize, usize), result: f32) {
+        let (key, symmetry) = Book::normalize(board, color);
+        let pos = super::pattern::transform(pos, symmetry);
+        let moves = self.entries.entry(key).or_insert_with(Vec::new);
+        match moves.iter_mut().find(|m| m.pos == pos) {
+            Some(m) => {
+                m.score = (m.score * m.weight as f32 + result) / (m.weight + 1) as f32;
+                m.weight += 1;
+            }
+            None => moves.push(BookMove {
+                pos: pos,
+                weight: 1,
+                score: result,
+            }),
+        }
+    }
+    /*
+        重みがmin_weight未満の手を削除する
+    */
+    pub fn prune(&mut self, min_weight: u32) {
+        for moves in self.entries.values_mut() {
+            moves.retain(|m| m.weight >= min_weight);
+        }
+        self.entries.retain(|_, moves| moves.len() != 0);
+    }
+    /*
+        登録されている手を実際の盤面上の座標に直して返す
+    */
+    pub fn candidates(&self, board: &Board, color: Stone) -> Vec<BookMove> {
+        let (key, symmetry) = Book::normalize(board, color);
+        let legal = super::can_set_pos(board, color);
+        let mut ret = Vec::new();
+        if let Some(moves) = self.entries.get(&key) {
+            for m in moves {
+                //対称変換で正規化した座標へ移る元の座標を探す
+                let pos = legal
+                    .iter()
+                    .find(|&&p| super::pattern::transform(p, symmetry) == m.pos);
+                if let Some(pos) = pos {
+                    ret.push(BookMove {
+                        pos: *pos,
+                        weight: m.weight,
+                        score: m.score,
+                    });
+                }
+            }
+        }
+        ret
+    }
+    /*
+        登録されている手の中から重みに比例した確率で一つ選ぶ
+        登録されていない局面ではNone
+    */
+    pub fn choose(
+        &self,
+        board: &Board,
+        color: Stone,
+        random: &mut GameRng,
+    ) -> Option<(usize, usize)> {
+        let candidates = self.candidates(board, color);
+        let total: u32 = candidates.iter().map(|m| m.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut r = random.gen_range(0, total);
+        for m in candidates.iter() {
+            if r < m.weight {
+                return Some(m.pos);
+            }
+            r -= m.weight;
+        }
+        None
+    }
+    pub fn to_text(&self) -> String {
+        let mut keys: Vec<&(u64, u64)> = self.entries.keys().collect();
+        keys.sort();
+        let mut ret = String::new();
+        for key in keys {
+            let mut line: String = (0..BOARDSIZE * BOARDSIZE)
+                .map(|i| {
+                    if key.0 >> i & 1 == 1 {
+                        'X'
+                    } else if key.1 >> i & 1 == 1 {
+                        'O'
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            for m in self.entries[key].iter() {
+                line += &format!(
+                    " {}:{}:{:.2}",
+                    Board::pos_to_notation(m.pos),
+                    m.weight,
+                    m.score
+                );
+            }
+            ret += &line;
+            ret.push('\n');
+        }
+        ret
+    }
+    pub fn from_text(text: &str) -> Result<Book, String> {
+        let mut book = Book::new();
+        for (i, line) in text.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            let board = match words.next() {
+                Some(board) => board,
+                None => continue,
+            };
+            let error = || format!("{}行目を解釈できません", i + 1);
+            let board = Board::from_text(board, 0, 0).ok_or_else(error)?;
+            //正規化済みの盤面なので，X(黒)が手番側となる
+            let (key, _) = Book::normalize(&board, Stone::Black);
+            let mut moves = Vec::new();
+            for word in words {
+                let items: Vec<&str> = word.split(':').collect();
+                if items.len() != 3 {
+                    return Err(error());
+                }
+                moves.push(BookMove {
+                    pos: Board::notation_to_pos(items[0]).ok_or_else(error)?,
+                    weight: items[1].parse().map_err(|_| error())?,
+                    score: items[2].parse().map_err(|_| error())?,
+                });
+            }
+            book.entries.insert(key, moves);
+        }
+        Ok(book)
+    }
+}
+
+/*
+    起動時にBOOK_FILEから読み込んだ定石を返す
+    ファイルがない，もしくは読み込めなかった場合はNone
+*/
+static SHARED: OnceLock<Option<Book>> = OnceLock::new();
+pub fn shared() -> Option<&'static Book> {
+    SHARED
+        .get_or_init(|| {
+            let book = std::fs::read_to_string(BOOK_FILE)
+                .map_err(|e| e.to_string())
+                .and_then(|text| Book::from_text(&text));
+            match book {
+                Ok(book) => Some(book),
+                Err(e) => {
+                    eprintln!(
+                        "定石({})を読み込めないため，定石なしで思考します: {}",
+                        BOOK_FILE, e
+                    );
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/*
+    棋譜の序盤depth手までを定石に登録する
+    終局まで打たれている棋譜は結果(石数差)も記録する．途中までの棋譜の結果は0とする
+    不正な棋譜の場合はfalse
+*/
+pub fn add_game(book: &mut Book, moves: &[(usize, usize)], depth: usize) -> bool {
+    let (last, last_color) = match record::replay(moves, 0, 0) {
+        Some(v) => v,
+        None => return false,
+    };
+    let finished = super::can_set_pos(&last, last_color).len() == 0;
+    for i in 0..std::cmp::min(depth, moves.len()) {
+        let (board, color) = record::replay(&moves[..i], 0, 0).unwrap();
+        let result = if finished {
+            let diff = last.white_num as f32 - last.black_num as f32;
+            match color {
+                Stone::White => diff,
+                _ => -diff,
+            }
+        } else {
+            0.0
+        };
+        book.add(&board, color, moves[i], result);
+    }
+    true
+}
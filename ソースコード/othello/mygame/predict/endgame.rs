@@ -0,0 +1,255 @@
+use super::montecarlo::MoveStat;
+use super::stats::{SearchMethod, SearchStats};
+use super::transposition::{Bound, TranspositionTable};
+use crate::mygame::{Board, Stone, BOARDSIZE};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/*
+    終盤の完全読み
+    空きマスが少ない局面で，最終的な石数差をαβ法(ネガマックス)で正確に求める
+    置換表には空きマスの数を深さとして保存する
+*/
+pub const SOLVE_EMPTIES: usize = 12; //完全読みを行う空きマスの数の目安
+
+/*
+    colorの手番の盤面を最後まで読み，(colorから見た最終的な石数差,最善手)を返す
+    置ける場所がない場合の最善手はNone
+*/
+pub fn solve(
+    board: &Board,
+    color: Stone,
+    table: &mut TranspositionTable,
+) -> (i32, Option<(usize, usize)>) {
+    table.new_search();
+    let bound = (BOARDSIZE * BOARDSIZE) as i32;
+    let never = AtomicBool::new(false);
+    let value = negamax(board, color, -bound, bound, table, false, &mut 0, &never);
+    let best = table.probe(board.hash(color)).and_then(|entry| entry.best);
+    (value, best)
+}
+
+/*
+    solveと同じく最後まで読み，探索の統計を返す
+    候補手ごとの石数差も求めるため，ルートの各手は全幅の窓で読む(solveより遅い)
+*/
+pub fn search(board: &Board, color: Stone, table: &mut TranspositionTable) -> SearchStats {
+    search_until(board, color, table, &AtomicBool::new(false)).unwrap()
+}
+/*
+    searchと同じ．別スレッドからstopが立てられたら読みを打ち切ってNoneを返す
+    打ち切った場合，tableには正しくない値が残るので捨てること
+*/
+pub fn search_until(
+    board: &Board,
+    color: Stone,
+    table: &mut TranspositionTable,
+    stop: &AtomicBool,
+) -> Option<SearchStats> {
+    let start = Instant::now();
+    table.new_search();
+    let bound = (BOARDSIZE * BOARDSIZE) as i32;
+    let mut stats = SearchStats::new(SearchMethod::Endgame);
+    let mut nodes = 0;
+    let list = super::can_set_pos(board, color);
+    if list.len() == 0 {
+        stats.score = Some(negamax(
+            board, color, -bound, bound, table, false, &mut nodes, stop,
+        ));
+    }
+    for pos in list {
+        let mut next = *board;
+        super::put_stone(&mut next, color, pos);
+        let before = nodes;
+        let value = -negamax(
+            &next,
+            color.return_reverse_color(),
+            -bound,
+            bound,
+            table,
+            false,
+            &mut nodes,
+            stop,
+        );
+        stats.children.push(MoveStat {
+            pos: pos,
+            visits: (nodes - before) as u32,
+            win_rate: if value > 0 {
+                1.0
+            } else if value == 0 {
+                0.5
+            } else {
+                0.0
+            },
+            score: Some(value),
+        });
+        if stats.score.map_or(true, |score| value > score) {
+            stats.score = Some(value);
+            stats.best = Some(pos);
+        }
+    }
+    if stop.load(Ordering::Relaxed) {
+        return None;
+    }
+    stats.nodes = nodes;
+    stats.max_depth = BOARDSIZE * BOARDSIZE - (board.black_num + board.white_num) as usize;
+    stats.pv = principal_variation(board, color, stats.best, table);
+    stats.elapsed = start.elapsed();
+    Some(stats)
+}
+/*
+    最善手をたどった読み筋
+    置換表のエントリは上書きされうるので，各局面の最善手は置換表を使って読み直して求める
+*/
+fn principal_variation(
+    board: &Board,
+    color: Stone,
+    first: Option<(usize, usize)>,
+    table: &mut TranspositionTable,
+) -> Vec<(usize, usize)> {
+    let mut pv = Vec::new();
+    let (mut board, mut color) = (*board, color);
+    let mut next = first;
+    loop {
+        if super::can_set_pos(&board, color).len() == 0 {
+            if super::can_set_pos(&board, color.return_reverse_color()).len() == 0 {
+                break; //終局
+            }
+            color.reversestone(); //パス
+            next = solve(&board, color, table).1;
+            continue;
+        }
+        match next {
+            Some(pos) if super::put_stone(&mut board, color, pos) => pv.push(pos),
+            _ => break,
+        }
+        color.reversestone();
+        next = solve(&board, color, table).1;
+    }
+    pv
+}
+
+fn negamax(
+    board: &Board,
+    color: Stone,
+    mut alpha: i32,
+    beta: i32,
+    table: &mut TranspositionTable,
+    passed: bool,
+    nodes: &mut usize,
+    stop: &AtomicBool,
+) -> i32 {
+    if stop.load(Ordering::Relaxed) {
+        return 0; //打ち切った(結果は使われない)
+    }
+    *nodes += 1;
+    let key = board.hash(color);
+    let alpha_orig = alpha;
+    let mut best_move = None;
+    if let Some(entry) = table.probe(key) {
+        if entry.cutoff(alpha as f32, beta as f32) {
+            return entry.value as i32;
+        }
+        best_move = entry.best;
+    }
+    let mut list = super::can_set_pos(board, color);
+    if list.len() == 0 {
+        if passed {
+            //両者とも置けないので終局
+            let diff = board.black_num as i32 - board.white_num as i32;
+            return match color {
+                Stone::Black => diff,
+                _ => -diff,
+            };
+        }
+        return -negamax(
+            board,
+            color.return_reverse_color(),
+            -beta,
+            -alpha,
+            table,
+            true,
+            nodes,
+            stop,
+        );
+    }
+    //前回の最善手から先に調べる
+    if let Some(pos) = best_move {
+        if let Some(i) = list.iter().position(|&p| p == pos) {
+            list.swap(0, i);
+        }
+    }
+    let mut best = (std::i32::MIN, list[0]);
+    for pos in list {
+        let mut next = *board;
+        super::put_stone(&mut next, color, pos);
+        let value = -negamax(
+            &next,
+            color.return_reverse_color(),
+            -beta,
+            -alpha,
+            table,
+            false,
+            nodes,
+            stop,
+        );
+        if value > best.0 {
+            best = (value, pos);
+        }
+        if value > alpha {
+            alpha = value;
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    let bound = if best.0 <= alpha_orig {
+        Bound::Upper
+    } else if best.0 >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    let empties = BOARDSIZE * BOARDSIZE - (board.black_num + board.white_num) as usize;
+    table.store(key, empties as u8, best.0 as f32, bound, Some(best.1));
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mygame::predict::transposition::TABLE_BITS;
+
+    //初期局面から，置ける最初の手を打ち続けて空きマスがempties個になった局面
+    fn late_position(empties: usize) -> (Board, Stone) {
+        let mut board = Board::new(BOARDSIZE, 0, 0);
+        let mut color = Stone::White;
+        board.count_stone();
+        while BOARDSIZE * BOARDSIZE - ((board.black_num + board.white_num) as usize) > empties {
+            let opponent = color.return_reverse_color();
+            match super::super::can_set_pos(&board, color).get(0) {
+                Some(&pos) => {
+                    super::super::put_stone(&mut board, color, pos);
+                }
+                None if super::super::can_set_pos(&board, opponent).len() == 0 => break, //終局
+                None => {}
+            }
+            color = opponent;
+        }
+        (board, color)
+    }
+
+    #[test]
+    fn search_agrees_with_solve() {
+        let (board, color) = late_position(10);
+        let stats = search(&board, color, &mut TranspositionTable::new(TABLE_BITS));
+        let (value, _) = solve(&board, color, &mut TranspositionTable::new(TABLE_BITS));
+        assert_eq!(stats.score, Some(value));
+    }
+    #[test]
+    fn stopped_search_returns_none() {
+        let (board, color) = late_position(10);
+        let mut table = TranspositionTable::new(TABLE_BITS);
+        assert!(search_until(&board, color, &mut table, &AtomicBool::new(true)).is_none());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::mygame::{Board, Stone, BOARDSIZE};
+
+/*
+    盤面の評価関数
+    各特徴量を「手番側から見た値 - 相手側から見た値」として求め，重み付きで足し合わせる
+    値が大きいほど手番側(color)が有利
+*/
+//各マスの重み(隅は高く，隅の隣は低い)
+const SQUARE_TABLE: [[f32; BOARDSIZE]; BOARDSIZE] = [
+    [100.0, -20.0, 10.0, 5.0, 5.0, 10.0, -20.0, 100.0],
+    [-20.0, -50.0, -2.0, -2.0, -2.0, -2.0, -50.0, -20.0],
+    [10.0, -2.0, 1.0, 1.0, 1.0, 1.0, -2.0, 10.0],
+    [5.0, -2.0, 1.0, 0.0, 0.0, 1.0, -2.0, 5.0],
+    [5.0, -2.0, 1.0, 0.0, 0.0, 1.0, -2.0, 5.0],
+    [10.0, -2.0, 1.0, 1.0, 1.0, 1.0, -2.0, 10.0],
+    [-20.0, -50.0, -2.0, -2.0, -2.0, -2.0, -50.0, -20.0],
+    [100.0, -20.0, 10.0, 5.0, 5.0, 10.0, -20.0, 100.0],
+];
+const CORNERS: [(usize, usize); 4] = [
+    (0, 0),
+    (0, BOARDSIZE - 1),
+    (BOARDSIZE - 1, 0),
+    (BOARDSIZE - 1, BOARDSIZE - 1),
+];
+//8方向
+const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/*
+    各特徴量の重み
+    "mobility=10,stable=20"のような文字列からも作成できる
+*/
+#[derive(Clone, Debug)]
+pub struct EvalWeights {
+    pub square: f32,             //マスの重みの合計
+    pub mobility: f32,           //置ける場所の数
+    pub potential_mobility: f32, //相手の石に隣接する空きマスの数
+    pub frontier: f32,           //空きマスに隣接する自分の石の数(少ないほど良い)
+    pub corner: f32,             //隅の石の数
+    pub x_square: f32,           //空いた隅の斜め隣の石の数(少ないほど良い)
+    pub c_square: f32,           //空いた隅の縦横隣の石の数(少ないほど良い)
+    pub stable: f32,             //確定石の数
+}
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            square: 1.0,
+            mobility: 8.0,
+            potential_mobility: 2.0,
+            frontier: 3.0,
+            corner: 30.0,
+            x_square: 15.0,
+            c_square: 5.0,
+            stable: 10.0,
+        }
+    }
+}
+impl EvalWeights {
+    pub fn parse(spec: &str) -> Result<EvalWeights, String> {
+        let mut weights = EvalWeights::default();
+        for item in spec.split(',').filter(|s| !s.is_empty()) {
+            let mut kv = item.splitn(2, '=');
+            let (key, value) = (kv.next().unwrap(), kv.next().unwrap_or(""));
+            let value: f32 = value
+                .parse()
+                .map_err(|_| format!("{}の値が不正です: {}", key, value))?;
+            match key {
+                "square" => weights.square = value,
+                "mobility" => weights.mobility = value,
+                "potential_mobility" => weights.potential_mobility = value,
+                "frontier" => weights.frontier = value,
+                "corner" => weights.corner = value,
+                "x_square" => weights.x_square = value,
+                "c_square" => weights.c_square = value,
+                "stable" => weights.stable = value,
+                _ => return Err(format!("不明な設定項目です: {}", key)),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+/*
+    手番側から見た各特徴量の値(手番側 - 相手側)
+    正負の向きは「大きいほど手番側が有利」にそろえてある
+*/
+#[derive(Clone, Debug, Default)]
+pub struct Features {
+    pub square: f32,
+    pub mobility: f32,
+    pub potential_mobility: f32,
+    pub frontier: f32,
+    pub corner: f32,
+    pub x_square: f32,
+    pub c_square: f32,
+    pub stable: f32,
+}
+impl Features {
+    pub fn new(board: &Board, color: Stone) -> Features {
+        let opp = color.return_reverse_color();
+        let g = &board.gamebord;
+        let mut f = Features::default();
+        f.mobility = (super::can_set_pos(board, color).len() as f32)
+            - (super::can_set_pos(board, opp).len() as f32);
+        let stable = stable_stones(g);
+        for y in 0..BOARDSIZE {
+            for x in 0..BOARDSIZE {
+                let sign = owner_sign(g[y][x], color);
+                if sign == 0.0 {
+                    //空きマス:隣接する石の持ち主ごとに，その相手の潜在的な着手可能数を数える
+                    let (mut next_to_mine, mut next_to_opp) = (false, false);
+                    for (ny, nx) in neighbors((y, x)) {
+                        let s = owner_sign(g[ny][nx], color);
+                        next_to_mine |= s > 0.0;
+                        next_to_opp |= s < 0.0;
+                    }
+                    if next_to_opp {
+                        f.potential_mobility += 1.0;
+                    }
+                    if next_to_mine {
+                        f.potential_mobility -= 1.0;
+                    }
+                    continue;
+                }
+                f.square += sign * SQUARE_TABLE[y][x];
+                if neighbors((y, x))
+                    .iter()
+                    .any(|&(ny, nx)| g[ny][nx] as i32 == Stone::Blank as i32)
+                {
+                    f.frontier -= sign;
+                }
+                if stable[y][x] {
+                    f.stable += sign;
+                }
+            }
+        }
+        for &(cy, cx) in CORNERS.iter() {
+            let sign = owner_sign(g[cy][cx], color);
+            if sign != 0.0 {
+                f.corner += sign;
+                continue;
+            }
+            //空いている隅の隣の石は，相手に隅を取られる原因になる
+            let dy: i32 = if cy == 0 { 1 } else { -1 };
+            let dx: i32 = if cx == 0 { 1 } else { -1 };
+            let at = |y: i32, x: i32| g[(cy as i32 + y) as usize][(cx as i32 + x) as usize];
+            f.x_square -= owner_sign(at(dy, dx), color);
+            f.c_square -= owner_sign(at(dy, 0), color) + owner_sign(at(0, dx), color);
+        }
+        f
+    }
+    pub fn score(&self, weights: &EvalWeights) -> f32 {
+        self.square * weights.square
+            + self.mobility * weights.mobility
+            + self.potential_mobility * weights.potential_mobility
+            + self.frontier * weights.frontier
+            + self.corner * weights.corner
+            + self.x_square * weights.x_square
+            + self.c_square * weights.c_square
+            + self.stable * weights.stable
+    }
+}
+
+/*
+    colorから見た盤面の評価値
+*/
+pub fn evaluate(board: &Board, color: Stone, weights: &EvalWeights) -> f32 {
+    Features::new(board, color).score(weights)
+}
+
+//石がcolorのものなら1，相手のものなら-1，空きなら0
+fn owner_sign(stone: Stone, color: Stone) -> f32 {
+    match stone {
+        Stone::Blank => 0.0,
+        _ if stone as i32 == color as i32 => 1.0,
+        _ => -1.0,
+    }
+}
+fn neighbors(pos: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut ret = Vec::with_capacity(8);
+    for (dy, dx) in DIRECTIONS.iter() {
+        let (ny, nx) = (pos.0 as i32 + dy, pos.1 as i32 + dx);
+        if 0 <= ny && ny < BOARDSIZE as i32 && 0 <= nx && nx < BOARDSIZE as i32 {
+            ret.push((ny as usize, nx as usize));
+        }
+    }
+    ret
+}
+
+/*
+    確定石(今後ひっくり返されることのない石)を求める
+    縦・横・斜め2方向の4つの軸それぞれで
+        ・その軸の列が全て埋まっている
+        ・軸方向のどちらかの隣が盤外
+        ・軸方向のどちらかの隣が同じ色の確定石
+    のいずれかを満たす石を確定石とし，変化がなくなるまで繰り返す
+*/
+pub fn stable_stones(g: &[[Stone; BOARDSIZE]; BOARDSIZE]) -> [[bool; BOARDSIZE]; BOARDSIZE] {
+    let mut stable = [[false; BOARDSIZE]; BOARDSIZE];
+    let axes: [(i32, i32); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+    let inside = |y: i32, x: i32| 0 <= y && y < BOARDSIZE as i32 && 0 <= x && x < BOARDSIZE as i32;
+    //軸ごとに，その列が全て埋まっているかを調べる
+    let line_full = |y: usize, x: usize, (dy, dx): (i32, i32)| -> bool {
+        for sign in [-1, 1].iter() {
+            let (mut ny, mut nx) = (y as i32, x as i32);
+            while inside(ny, nx) {
+                if g[ny as usize][nx as usize] as i32 == Stone::Blank as i32 {
+                    return false;
+                }
+                ny += dy * sign;
+                nx += dx * sign;
+            }
+        }
+        true
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for y in 0..BOARDSIZE {
+            for x in 0..BOARDSIZE {
+                if stable[y][x] || g[y][x] as i32 == Stone::Blank as i32 {
+                    continue;
+                }
+                let color = g[y][x] as i32;
+                let ok = axes.iter().all(|&(dy, dx)| {
+                    let sides = [
+                        (y as i32 - dy, x as i32 - dx),
+                        (y as i32 + dy, x as i32 + dx),
+                    ];
+                    sides.iter().any(|&(ny, nx)| {
+                        !inside(ny, nx)
+                            || (stable[ny as usize][nx as usize]
+                                && g[ny as usize][nx as usize] as i32 == color)
+                    }) || line_full(y, x, (dy, dx))
+                });
+                if ok {
+                    stable[y][x] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+    stable
+}
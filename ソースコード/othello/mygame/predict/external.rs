@@ -0,0 +1,467 @@
+use super::montecarlo::SearchTag;
+use super::stats::SearchStats;
+use crate::mygame::{random, Board, Stone, BOARDSIZE};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/*
+    外部の思考エンジンを子プロセスとして起動し，標準入出力の1行ごとのテキストで対話する
+
+    GUI -> エンジン
+        isready                    : 起動確認．readyokを返すこと
+        position <盤面64文字> <X|O> : 盤面(Board::to_textの形式)と手番(X:黒 O:白)を設定
+        go <ミリ秒>                 : 思考時間の目安を与えて着手を要求
+        quit                       : 終了
+    エンジン -> GUI
+        readyok
+        info ...                   : 任意．思考の統計(stats.rsのSearchStats::to_infoの形式)
+        move <d3等の座標|pass>
+    上記以外の行は無視する
+*/
+const EXTERNAL_THINK_TIME: u64 = 1000; //エンジンに渡す思考時間の目安(ミリ秒)
+const EXTERNAL_TIMEOUT_MARGIN: u64 = 5000; //思考時間を超えてこの時間応答がなければタイムアウト(ミリ秒)
+const EXTERNAL_READY_TIMEOUT: u64 = 5000; //起動時の応答待ち時間(ミリ秒)
+
+#[derive(Debug)]
+pub enum EngineError {
+    Spawn(io::Error), //プロセスを起動できなかった
+    Io(io::Error),    //書き込みに失敗した
+    Timeout,          //制限時間内に応答がなかった
+    Crashed,          //プロセスが終了した
+    Protocol(String), //応答が解釈できない，もしくは不正な着手
+}
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EngineError::Spawn(e) => write!(f, "エンジンを起動できません: {}", e),
+            EngineError::Io(e) => write!(f, "エンジンとの通信に失敗しました: {}", e),
+            EngineError::Timeout => write!(f, "エンジンが時間内に応答しませんでした"),
+            EngineError::Crashed => write!(f, "エンジンが終了しました"),
+            EngineError::Protocol(line) => write!(f, "エンジンの応答が不正です: {}", line),
+        }
+    }
+}
+
+pub struct ExternalEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,    //エンジンの標準出力を1行ずつ受け取る
+    stats: Option<SearchStats>, //最後の思考でエンジンが出力したinfo行
+}
+impl ExternalEngine {
+    /*
+        エンジンを起動し，isreadyに応答するまで待つ
+    */
+    pub fn spawn(command: &str, args: &[String]) -> Result<ExternalEngine, EngineError> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(EngineError::Spawn)?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        //標準出力の読み込みはブロックするため，別スレッドで行いチャネル経由で受け取る
+        //プロセスが終了するとsenderが破棄され，受信側で検知できる
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            return;
+                        }
+                    }
+                    Err(_) => return,
+                }
+            }
+        });
+        let mut engine = ExternalEngine {
+            child: child,
+            stdin: stdin,
+            lines: lines,
+            stats: None,
+        };
+        engine.send("isready")?;
+        engine.wait_for("readyok", Duration::from_millis(EXTERNAL_READY_TIMEOUT))?;
+        Ok(engine)
+    }
+    fn send(&mut self, line: &str) -> Result<(), EngineError> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::BrokenPipe => EngineError::Crashed,
+                _ => EngineError::Io(e),
+            })
+    }
+    /*
+        keywordで始まる行が来るまで待ち，keywordより後ろの部分を返す
+        途中のinfo行は思考の統計として覚えておく
+    */
+    fn wait_for(&mut self, keyword: &str, timeout: Duration) -> Result<String, EngineError> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let rest = deadline.saturating_duration_since(std::time::Instant::now());
+            match self.lines.recv_timeout(rest) {
+                Ok(line) => {
+                    if let Some(stats) = SearchStats::parse_info(&line) {
+                        self.stats = Some(stats);
+                        continue;
+                    }
+                    let mut words = line.split_whitespace();
+                    if words.next() == Some(keyword) {
+                        return Ok(words.collect::<Vec<&str>>().join(" "));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Err(EngineError::Timeout),
+                Err(RecvTimeoutError::Disconnected) => return Err(EngineError::Crashed),
+            }
+        }
+    }
+    /*
+        盤面と手番を送り，エンジンの着手を受け取る
+        None:パス
+    */
+    pub fn request_move(
+        &mut self,
+        board: &Board,
+        color: Stone,
+    ) -> Result<Option<(usize, usize)>, EngineError> {
+        let side = match color {
+            Stone::Black => "X",
+            _ => "O",
+        };
+        self.stats = None;
+        self.send(&format!("position {} {}", board.to_text(), side))?;
+        self.send(&format!("go {}", EXTERNAL_THINK_TIME))?;
+        let answer = self.wait_for(
+            "move",
+            Duration::from_millis(EXTERNAL_THINK_TIME + EXTERNAL_TIMEOUT_MARGIN),
+        )?;
+        //エンジンの着手が合法手かを確認する
+        let legal = super::can_set_pos(board, color);
+        if answer == "pass" {
+            if legal.len() == 0 {
+                return Ok(None);
+            }
+            return Err(EngineError::Protocol(format!("move {}", answer)));
+        }
+        match Board::notation_to_pos(&answer) {
+            Some(pos) if legal.contains(&pos) => Ok(Some(pos)),
+            _ => Err(EngineError::Protocol(format!("move {}", answer))),
+        }
+    }
+    //最後のrequest_moveでエンジンが出力した思考の統計
+    pub fn last_stats(&self) -> Option<SearchStats> {
+        self.stats.clone()
+    }
+}
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        //終了を依頼し，応じなかった場合に備えてプロセスを終了させる
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/*
+    ExternalEngineを別スレッドで動かし，montecarlotreeと同じ形式で結果を返す
+    依頼ごとにSearchTagを付け，結果を受け取ったときに局面が変わっていた場合は捨てて依頼し直す
+    エンジンに異常が起きた場合は，以降は組み込みのモンテカルロ木探索で代わりに思考する
+*/
+type EngineResult = Result<Option<(usize, usize)>, EngineError>;
+pub struct ExternalPlayer {
+    engine: Option<ExternalEngine>,
+    waiting: Option<Receiver<(SearchTag, ExternalEngine, EngineResult)>>,
+    tag: Option<SearchTag>, //最後に依頼した思考
+    position: Option<Option<(usize, usize)>>,
+    stats: Option<SearchStats>,
+    failed: bool,
+}
+impl ExternalPlayer {
+    pub fn new(engine: ExternalEngine) -> ExternalPlayer {
+        ExternalPlayer {
+            engine: Some(engine),
+            waiting: None,
+            tag: None,
+            position: None,
+            stats: None,
+            failed: false,
+        }
+    }
+    pub fn think(
+        &mut self,
+        board: &Board,
+        color: Stone,
+        status: &crate::mygame::detailedstatus::Status,
+        seed: u64, //代わりに組み込みの探索で思考する場合のみ使う
+    ) -> Option<Option<(usize, usize)>> {
+        /*
+            None:現在思考中
+            Some(None):おける場所なし
+            Some(Some(T)):Tへと置く
+        */
+        if self.failed {
+            return super::montecarlo::montecarlotree(
+                board,
+                color,
+                status,
+                &super::EngineConfig::default(),
+                seed,
+            );
+        }
+        //別の局面の思考の結果は捨てる(思考中の場合は，結果を受け取ってから捨てる)
+        let key = board.hash(color);
+        if self.tag.map_or(true, |tag| tag.key != key) {
+            self.position = None;
+        }
+        if let Some(receiver) = &self.waiting {
+            match receiver.try_recv() {
+                Ok((tag, engine, Ok(pos))) => {
+                    if self.tag == Some(tag) && tag.key == key {
+                        self.stats = engine.last_stats();
+                        self.position = Some(pos);
+                    }
+                    self.engine = Some(engine);
+                    self.waiting = None;
+                }
+                Ok((_tag, _engine, Err(e))) => {
+                    //_engineはここで破棄され，プロセスも終了する
+                    eprintln!("{}", e);
+                    self.fail();
+                    return None;
+                }
+                Err(mpsc::TryRecvError::Empty) => return None,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    eprintln!("{}", EngineError::Crashed);
+                    self.fail();
+                    return None;
+                }
+            }
+        }
+        if let Some(pos) = self.position {
+            if !status.rotating {
+                //値をリセットするのは，実際に行動できる，回転中の石がある場合のみ
+                self.position = None;
+            }
+            return Some(pos);
+        }
+        let mut engine = match self.engine.take() {
+            Some(engine) => engine,
+            None => {
+                self.fail();
+                return None;
+            }
+        };
+        let tag = SearchTag::next(board, color);
+        self.tag = Some(tag);
+        let board = *board;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = engine.request_move(&board, color);
+            let _ = sender.send((tag, engine, result));
+        });
+        self.waiting = Some(receiver);
+        None
+    }
+    /*
+        最後に終わった思考の統計
+        組み込みのモンテカルロ木探索に切り替えた後はその統計を返す
+    */
+    pub fn last_stats(&self) -> Option<SearchStats> {
+        if self.failed {
+            return super::montecarlo::last_stats();
+        }
+        self.stats.clone()
+    }
+    fn fail(&mut self) {
+        eprintln!("外部エンジンを停止し，モンテカルロ木探索に切り替えます");
+        self.engine = None;
+        self.waiting = None;
+        self.tag = None;
+        self.position = None;
+        self.failed = true;
+    }
+}
+
+/*
+    自身をエンジンとして動かす(--engine-mode)
+    上記のプロトコルを標準入出力で受け付け，モンテカルロ木探索で応答する
+    着手の前に思考の統計をinfo行で出力する
+    外部エンジン連携の動作確認用のスタブとしても使う
+    乱数のシードは起動時に標準エラー出力へ表示する
+*/
+pub fn run_engine_mode(config: super::EngineConfig) {
+    let seed = random::new_seed();
+    eprintln!("シード: {}", seed);
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut board = Board::new(BOARDSIZE, 0, 0);
+    board.count_stone();
+    let mut color = Stone::White;
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let reply = match words.get(0) {
+            Some(&"isready") => Some("readyok".to_string()),
+            Some(&"position") if words.len() == 3 => {
+                match Board::from_text(words[1], 0, 0) {
+                    Some(b) => board = b,
+                    None => eprintln!("盤面を解釈できません: {}", words[1]),
+                }
+                color = if words[2] == "X" {
+                    Stone::Black
+                } else {
+                    Stone::White
+                };
+                None
+            }
+            Some(&"go") => {
+                let moves = (board.black_num + board.white_num) as u64;
+                let mut random = random::from_seed(random::derive(seed, moves));
+                let stats = super::search(&board, color, &config, &mut random);
+                let answer = match stats.best {
+                    Some(pos) => format!("move {}", Board::pos_to_notation(pos)),
+                    None => "move pass".to_string(),
+                };
+                Some(format!("{}\n{}", stats.to_info(), answer))
+            }
+            Some(&"quit") => return,
+            _ => None,
+        };
+        if let Some(reply) = reply {
+            if writeln!(out, "{}", reply)
+                .and_then(|_| out.flush())
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    /*
+        シェルスクリプトのスタブをエンジンとして起動し，異常時の扱いを確かめる
+        スタブはisreadyにreadyokを返し，goを受け取るとgo_actionを実行する
+    */
+    use super::*;
+    use crate::mygame::detailedstatus::Status;
+    use std::time::Instant;
+
+    fn stub(go_action: &str) -> Result<ExternalEngine, EngineError> {
+        let script = format!(
+            "while read line; do case \"$line\" in isready) echo readyok;; go*) {};; quit) exit 0;; esac; done",
+            go_action
+        );
+        ExternalEngine::spawn("sh", &["-c".to_string(), script])
+    }
+    fn initial_board() -> Board {
+        let mut board = Board::new(BOARDSIZE, 0, 0);
+        board.count_stone();
+        board
+    }
+
+    #[test]
+    fn legal_move_is_accepted() {
+        let board = initial_board();
+        let pos = super::super::can_set_pos(&board, Stone::White)[0];
+        let mut engine = stub(&format!("echo \"move {}\"", Board::pos_to_notation(pos))).unwrap();
+        assert_eq!(
+            engine.request_move(&board, Stone::White).unwrap(),
+            Some(pos)
+        );
+    }
+    #[test]
+    fn illegal_move_is_rejected() {
+        let board = initial_board();
+        let mut engine = stub("echo \"move a1\"").unwrap();
+        match engine.request_move(&board, Stone::White) {
+            Err(EngineError::Protocol(_)) => {}
+            other => panic!("不正な着手が受け付けられました: {:?}", other),
+        }
+    }
+    #[test]
+    fn crash_is_detected() {
+        let board = initial_board();
+        let mut engine = stub("exit 1").unwrap();
+        match engine.request_move(&board, Stone::White) {
+            Err(EngineError::Crashed) => {}
+            other => panic!("エンジンの終了を検知できません: {:?}", other),
+        }
+    }
+    #[test]
+    fn stall_times_out() {
+        let board = initial_board();
+        let mut engine = stub("sleep 30").unwrap();
+        match engine.request_move(&board, Stone::White) {
+            Err(EngineError::Timeout) => {}
+            other => panic!("タイムアウトしません: {:?}", other),
+        }
+    }
+    #[test]
+    fn missing_readyok_fails_to_spawn() {
+        match ExternalEngine::spawn("sh", &["-c".to_string(), "sleep 30".to_string()]) {
+            Err(EngineError::Timeout) => {}
+            Err(e) => panic!("起動時のエラーが異なります: {}", e),
+            Ok(_) => panic!("readyokを返さないエンジンが起動できました"),
+        }
+    }
+    #[test]
+    fn player_falls_back_to_mcts() {
+        let board = initial_board();
+        let mut player = ExternalPlayer::new(stub("exit 1").unwrap());
+        let status = Status::new(0.0);
+        let start = Instant::now();
+        let pos = loop {
+            if let Some(pos) = player.think(&board, Stone::White, &status, 1) {
+                break pos;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(60),
+                "思考が終わりません"
+            );
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert!(player.failed);
+        let pos = pos.expect("置ける場所があるのにパスしました");
+        assert!(super::super::can_set_pos(&board, Stone::White).contains(&pos));
+    }
+    #[test]
+    fn result_for_old_position_is_discarded() {
+        //一度目の依頼(白番)の結果が返る前に局面(黒番)が変わった場合，一度目の結果は打たずに依頼し直す
+        let board = initial_board();
+        let white = super::super::can_set_pos(&board, Stone::White)[0];
+        let black = super::super::can_set_pos(&board, Stone::Black)[0];
+        let go = format!(
+            "n=$((n+1)); if [ $n = 1 ]; then sleep 1; echo \"move {}\"; else echo \"move {}\"; fi",
+            Board::pos_to_notation(white),
+            Board::pos_to_notation(black)
+        );
+        let mut player = ExternalPlayer::new(stub(&go).unwrap());
+        let status = Status::new(0.0);
+        assert_eq!(player.think(&board, Stone::White, &status, 1), None);
+        let start = Instant::now();
+        let pos = loop {
+            if let Some(pos) = player.think(&board, Stone::Black, &status, 1) {
+                break pos;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(60),
+                "思考が終わりません"
+            );
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert!(!player.failed);
+        assert_eq!(pos, Some(black));
+    }
+}
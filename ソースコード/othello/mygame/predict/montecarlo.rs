@@ -1,53 +1,58 @@
+use super::stats::{SearchMethod, SearchStats};
+use crate::mygame::random::{self, GameRng};
 use crate::mygame::{Board, Stone, *};
 use rand::Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::thread;
 use std::time::Instant;
 
 /*
     CPUの思考にはモンテカルロ木探索を使用
+    手順違いで同じ局面(手番も同じ)に至った場合は，Zobristハッシュで見つけた既存のノードを共有する
+    そのためゲーム木は親を複数持ちうるので，逆伝播は選択時にたどった経路に沿って行う
+    ノードの共有にはtransposition::TranspositionTableではなく，局面からノード番号へのHashMapを使う
+    ノードは一回の試行で高々一つしか増えないので，HashMapの大きさもゲーム木と同じくmaxtryまでに収まる
+    固定の大きさの置換表では置き換えられたノードを共有できなくなり，統計を分散させるだけになる
 */
 // ゲーム木のノード
 #[derive(Clone)]
 struct Node {
-    id: usize,
     board: Board,
-    parent: Option<usize>,
-    color: Stone,
+    color: Stone, //このノードの局面へ至る手を打った側
     childrens: Vec<usize>,
-    moves: Option<(usize, usize)>,
+    moves: Option<(usize, usize)>, //このノードを作成したときの手(共有されたノードでは親によって異なりうる)
     win: f32,
     visit: f32,
     unusedmoves: Vec<(usize, usize)>,
 }
 impl Node {
-    pub fn new(id: usize, mov: (usize, usize), board: &Board, parent: usize, color: Stone) -> Self {
+    pub fn new(mov: (usize, usize), board: &Board, color: Stone) -> Self {
         Node {
-            id: id,
             board: *board,
-            parent: Some(parent),
             color: color,
             childrens: Vec::new(),
             moves: Some(mov),
             win: 0.0,
             visit: 0.0,
-            unusedmoves: super::can_set_pos(board, color),
+            unusedmoves: super::can_set_pos(board, color.return_reverse_color()), //次に打つのは相手側
         }
     }
     /*
         子のノードの中で下記の数式が最大となるノードを選ぶ　数式はモンテカルロ木探索AIで最も慣例的に使われている物を使用
-        win/visit+π*sqrt(ln(N)/visit) (Nは親ノードの試行回数)
+        win/visit+c*sqrt(ln(N)/visit) (Nは親ノードの試行回数，cはEngineConfig::exploration)
 
     */
-    fn select_child(&self, trees: &Vec<Node>) -> Option<usize> {
+    fn select_child(&self, trees: &Vec<Node>, exploration: f32) -> Option<usize> {
         if self.childrens.len() == 0 {
             return None;
         }
         let (mut ret_id, mut mx_score) = (0, -1e9);
         for id in self.childrens.iter() {
-            let pi = std::f32::consts::PI;
             let node = &trees[*id];
             let score = (node.win as f32) / (node.visit as f32)
-                + pi * ((self.visit as f32).ln() / (node.visit as f32)).sqrt();
+                + exploration * ((self.visit as f32).ln() / (node.visit as f32)).sqrt();
             if score > mx_score {
                 ret_id = *id;
                 mx_score = score;
@@ -55,30 +60,40 @@ impl Node {
         }
         Some(ret_id)
     }
+    /*
+        局面と手番(このノードから次に打つ側)から求めた，置換を見つけるためのキー
+    */
+    fn key(&self) -> u64 {
+        self.board.hash(self.color.return_reverse_color())
+    }
     /*
         まだ行動可能な場合，自身の子でゲーム木が存在しない物をランダムに選択し，ゲーム木を作成する
+        作成した局面が既にゲーム木にある場合は，新たに作らずそのノードを子とする
+        (子のノード番号,選んだ手のunusedmoves内の位置)を返す
     */
-    fn expand_child(&self, trees: &mut Vec<Node>) -> Option<(Node, usize)> {
+    fn expand_child(
+        &self,
+        trees: &mut Vec<Node>,
+        table: &mut HashMap<u64, usize>,
+        random: &mut GameRng,
+    ) -> Option<(usize, usize)> {
         if self.unusedmoves.len() == 0 {
             return None;
         }
 
-        let random_id = rand::thread_rng().gen_range(0, self.unusedmoves.len());
+        let random_id = random.gen_range(0, self.unusedmoves.len());
         let mut board = self.board.clone();
         let color = self.color.return_reverse_color();
         board.setstone(
             &super::can_cnt(&board.gamebord, color, self.unusedmoves[random_id], false),
             color,
         );
-        let tree = Node::new(
-            trees.len(),
-            self.unusedmoves[random_id],
-            &board,
-            self.id,
-            color,
-        );
-        trees.push(tree.clone());
-        Some((tree, random_id))
+        let tree = Node::new(self.unusedmoves[random_id], &board, color);
+        let id = *table.entry(tree.key()).or_insert(trees.len());
+        if id == trees.len() {
+            trees.push(tree);
+        }
+        Some((id, random_id))
     }
     /*
         expand_childで作成したゲーム木への行動を削除する
@@ -87,11 +102,23 @@ impl Node {
         self.unusedmoves.remove(id);
     }
     /*
-        勝敗が決定するまでお互いランダムに打つ
+        勝敗が決定するまでお互いplayoutに従って打つ
     */
-    fn simlate(&self, color: Stone) -> f32 {
+    fn simlate(
+        &self,
+        color: Stone,
+        playout: &super::Playout,
+        eval: &super::PlayoutEval,
+        random: &mut GameRng,
+    ) -> f32 {
         let mut board = self.board;
-        super::randommove(&mut board, self.color.return_reverse_color());
+        super::randommove(
+            &mut board,
+            self.color.return_reverse_color(),
+            playout,
+            eval,
+            random,
+        );
 
         match board.return_win() {
             Stone::Black => match color {
@@ -106,112 +133,313 @@ impl Node {
         }
     }
 }
+/*
+    思考の依頼を区別する印
+    探索する局面(Board::hash)と，依頼するごとに増やす世代の組
+    結果を受け取るときに今の局面・最後の依頼と比べ，違う場合は結果を捨てる
+*/
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SearchTag {
+    pub key: u64,
+    pub generation: u64,
+}
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+impl SearchTag {
+    //colorの手番のboardの思考を新しく依頼するときの印
+    pub fn next(board: &Board, color: Stone) -> SearchTag {
+        SearchTag {
+            key: board.hash(color),
+            generation: GENERATION.fetch_add(1, Ordering::SeqCst) + 1,
+        }
+    }
+    //最後に依頼した思考か
+    fn is_latest(&self) -> bool {
+        GENERATION.load(Ordering::SeqCst) == self.generation
+    }
+}
+/*
+    montecarlotreeで最後に始めた探索
+    position:探索の結果(探索中はNone)
+*/
+#[derive(Clone, Copy)]
+struct Search {
+    tag: SearchTag,
+    position: Option<Option<(usize, usize)>>,
+}
+static SEARCH: Mutex<Option<Search>> = Mutex::new(None);
 //一回あたりの探索上限回数
+pub const MAXTRY: usize = 4000;
 /*
-    staticな変数として，現在行動探索を行っているスレッドが存在するか，
-    探索が終わっている場合の探索結果を持つpairの2つを使用している
+    montecarlotreeで思考中の探索の途中経過(GUIでの表示用)
+    思考していない間はNone
 */
-static mut POSITION: Option<Option<(usize, usize)>> = None;
-static mut PREDICT_END: bool = true;
-const MAXTRY: usize = 4000;
+#[derive(Clone)]
+pub struct SearchProgress {
+    pub moves: Vec<MoveStat>,
+    pub iterations: usize,
+    pub start: Instant, //思考を始めた時刻
+}
+static PROGRESS: Mutex<Option<SearchProgress>> = Mutex::new(None);
+pub fn progress() -> Option<SearchProgress> {
+    PROGRESS.lock().unwrap().clone()
+}
+//montecarlotreeで最後に終わった思考の統計
+static LAST_STATS: Mutex<Option<SearchStats>> = Mutex::new(None);
+pub fn last_stats() -> Option<SearchStats> {
+    LAST_STATS.lock().unwrap().clone()
+}
 pub fn montecarlotree(
     board2: &Board,
     color: Stone,
     status: &crate::mygame::detailedstatus::Status,
+    config: &super::EngineConfig,
+    seed: u64,
 ) -> Option<Option<(usize, usize)>> {
     /*
         None:現在思考中
         Some(None):おける場所なし
         Some(Some(T)):Tへと置く
     */
-    unsafe {
-        //static変数の書き換えと参照はRustではunsafeとなる
-        if PREDICT_END == false {
-            return None; //思考中
-        }
-        match POSITION {
-            None => {}
-            _ => {
-                let ret = POSITION.clone();
-                if !status.rotating {
-                    //値をリセットするのは，実際に行動できる，回転中の石がある場合のみ
-                    POSITION = None;
+    let tag = {
+        let mut search = SEARCH.lock().unwrap();
+        if let Some(current) = *search {
+            if current.tag.key == board2.hash(color) {
+                match current.position {
+                    None => return None, //思考中
+                    Some(ret) => {
+                        if !status.rotating {
+                            //値をリセットするのは，実際に行動できる，回転中の石がある場合のみ
+                            *search = None;
+                        }
+                        return Some(ret);
+                    }
                 }
-                return ret;
             }
         }
-        PREDICT_END = false;
-    }
+        //探索していないか，別の局面を探索していた(その探索は打ち切り，結果は捨てる)
+        let tag = SearchTag::next(board2, color);
+        *search = Some(Search {
+            tag: tag,
+            position: None,
+        });
+        tag
+    };
     let board = board2.clone();
+    let config = config.clone();
     thread::spawn(move || {
-        //
-        let start = Instant::now(); //予測にかかる時間の計測
-        let mov = super::can_set_pos(&board, color);
-        if mov.len() == 0 {
-            unsafe {
-                POSITION = Some(None);
-                PREDICT_END = true;
-            }
-            return; //置ける場所なし
-        }
-        //現在のゲーム木を作成
-        let root = Node {
-            id: 0,
-            board: board,
-            childrens: Vec::<usize>::with_capacity(64),
-            color: color.return_reverse_color(),
-            moves: None,
-            parent: None,
-            visit: 0.0,
-            win: 0.0,
-            unusedmoves: mov,
-        };
-        let mut tree: Vec<Node> = Vec::<Node>::with_capacity(2048);
-        tree.push(root);
-        //最大試行回数まで探索を行う
-        for _ in 0..MAXTRY {
-            let mut node_id = 0;
-            while tree[node_id].unusedmoves.len() == 0 && tree[node_id].childrens.len() != 0 {
-                node_id = tree[node_id].select_child(&tree).unwrap();
-            }
-            if tree[node_id].unusedmoves.len() != 0 {
-                let val = tree[node_id].clone();
-                let (_node, id) = val.expand_child(&mut tree).unwrap();
-                let num = tree.len().clone();
-                tree[node_id].deleteunusedmoves(id);
-                tree[node_id].childrens.push(num - 1);
-                node_id = num - 1;
-            }
-            let won = tree[node_id].simlate(tree[1].color);
-            let mut id_list: Vec<usize> = vec![node_id];
-            while tree[node_id].parent != None {
-                let id = tree[node_id].parent.unwrap();
-                id_list.push(id);
-                node_id = id;
-            }
-            for iter in id_list {
-                tree[iter].win += won;
-                tree[iter].visit += 1.0;
+        *PROGRESS.lock().unwrap() = Some(SearchProgress {
+            moves: Vec::new(),
+            iterations: 0,
+            start: Instant::now(),
+        });
+        let stats = super::search_with_report(
+            &board,
+            color,
+            &config,
+            &mut random::from_seed(seed),
+            &mut |stats| {
+                if !tag.is_latest() {
+                    return false; //後から別の探索が始まった
+                }
+                if let Some(progress) = PROGRESS.lock().unwrap().as_mut() {
+                    progress.moves = stats.children.clone();
+                    progress.iterations = stats.iterations;
+                }
+                true
+            },
+        );
+        let mut search = SEARCH.lock().unwrap();
+        match search.as_mut() {
+            Some(current) if current.tag == tag => {
+                *PROGRESS.lock().unwrap() = None;
+                current.position = Some(stats.best);
+                *LAST_STATS.lock().unwrap() = Some(stats);
             }
+            _ => {} //古い探索の結果は捨てる
         }
-        //現在のゲーム木が持つ子ノードの中で，一番試行回数が大きいものを探索結果とする
-        let (mut ret, mut mx_score) = ((0, 0), 0.0);
-        for i in tree[0].childrens.iter() {
-            if tree[*i].visit > mx_score {
-                ret = tree[*i].moves.unwrap();
-                mx_score = tree[*i].visit;
+    });
+    None
+}
+/*
+    与えられた盤面に対してモンテカルロ木探索をconfig.maxtry回(config.time_limitを過ぎたらそこまで)行い，
+    置く位置と探索の統計を返す
+    探索は呼び出し元のスレッドで行い，乱数は全てrandomから取る
+    置ける場所がない場合はstats.bestがNone
+*/
+pub fn search(
+    board: &Board,
+    color: Stone,
+    config: &super::EngineConfig,
+    random: &mut GameRng,
+) -> SearchStats {
+    search_with_report(board, color, config, random, &mut |_| true)
+}
+
+/*
+    探索途中の候補手(ルートの子)一つ分の統計
+*/
+#[derive(Clone, Debug)]
+pub struct MoveStat {
+    pub pos: (usize, usize),
+    pub visits: u32,
+    pub win_rate: f32,      //手番側から見た勝率(0〜1)
+    pub score: Option<i32>, //完全読みで求めた手番側から見た石数差(完全読みの場合のみ)
+}
+//探索途中の統計を知らせる間隔(試行回数)
+const REPORT_INTERVAL: usize = 200;
+/*
+    searchと同じ探索を行い，REPORT_INTERVAL回ごとと探索の終了時に
+    report(その時点の統計)を呼ぶ
+    reportがfalseを返した場合は，そこで探索を打ち切る
+*/
+pub fn search_with_report(
+    board: &Board,
+    color: Stone,
+    config: &super::EngineConfig,
+    random: &mut GameRng,
+    report: &mut dyn FnMut(&SearchStats) -> bool,
+) -> SearchStats {
+    let board = *board;
+    let mut result = SearchStats::new(SearchMethod::MonteCarlo);
+    let mov = super::can_set_pos(&board, color);
+    if mov.len() == 0 {
+        return result; //置ける場所なし
+    }
+    //現在のゲーム木を作成
+    let root = Node {
+        board: board,
+        childrens: Vec::<usize>::with_capacity(64),
+        color: color.return_reverse_color(),
+        moves: None,
+        visit: 0.0,
+        win: 0.0,
+        unusedmoves: mov,
+    };
+    let mut tree: Vec<Node> = Vec::<Node>::with_capacity(2048);
+    let mut table: HashMap<u64, usize> = HashMap::with_capacity(2048);
+    table.insert(root.key(), 0);
+    tree.push(root);
+    let eval = super::PlayoutEval::new();
+    //最大試行回数まで探索を行う
+    let start = Instant::now();
+    for i in 0..config.maxtry {
+        if let Some(limit) = config.time_limit {
+            //最低一回は探索しないと置く位置が決まらない
+            if i != 0 && start.elapsed() >= limit {
+                break;
             }
         }
-        unsafe {
-            POSITION = Some(Some(ret));
-            PREDICT_END = true;
+        if i != 0 && i % REPORT_INTERVAL == 0 && !report(&collect_stats(&tree, &result, start)) {
+            break;
+        }
+        let mut node_id = 0;
+        let mut id_list: Vec<usize> = vec![node_id];
+        while tree[node_id].unusedmoves.len() == 0 && tree[node_id].childrens.len() != 0 {
+            node_id = tree[node_id]
+                .select_child(&tree, config.exploration)
+                .unwrap();
+            id_list.push(node_id);
+        }
+        if tree[node_id].unusedmoves.len() != 0 {
+            let val = tree[node_id].clone();
+            let (child, id) = val.expand_child(&mut tree, &mut table, random).unwrap();
+            tree[node_id].deleteunusedmoves(id);
+            tree[node_id].childrens.push(child);
+            node_id = child;
+            id_list.push(node_id);
         }
-        let end = start.elapsed();
-        println!(
-            "モンテカルロ木探索予測時間 :{}.{:03}秒",
-            end.as_secs(),
-            end.subsec_nanos() / 1_000_000
+        result.max_depth = std::cmp::max(result.max_depth, id_list.len() - 1);
+        let won = tree[node_id].simlate(tree[1].color, &config.playout, &eval, random);
+        for iter in id_list {
+            tree[iter].win += won;
+            tree[iter].visit += 1.0;
+        }
+    }
+    let result = collect_stats(&tree, &result, start);
+    report(&result);
+    result
+}
+/*
+    ゲーム木からその時点の統計を作る(max_depthは探索中にresultへ記録したもの)
+*/
+fn collect_stats(tree: &Vec<Node>, result: &SearchStats, start: Instant) -> SearchStats {
+    let stats = root_stats(tree);
+    //現在のゲーム木が持つ子ノードの中で，一番試行回数が大きいものを探索結果とする
+    //まだ一度も試行していない場合は，置ける場所のうち最初の手とする
+    let (mut ret, mut mx_score) = (tree[0].unusedmoves.get(0).copied(), 0.0);
+    for stat in stats.iter() {
+        if stat.visits as f32 > mx_score {
+            ret = Some(stat.pos);
+            mx_score = stat.visits as f32;
+        }
+    }
+    let mut result = result.clone();
+    result.best = ret;
+    result.nodes = tree.len();
+    result.iterations = tree[0].visit as usize;
+    result.elapsed = start.elapsed();
+    result.pv = principal_variation(tree);
+    result.children = stats;
+    result
+}
+/*
+    ルートから試行回数が最も多い子をたどった手順
+    共有されたノードのmovesは別の親からの手でありうるので，手は親子の盤面の差から求める
+*/
+fn principal_variation(tree: &Vec<Node>) -> Vec<(usize, usize)> {
+    let mut pv = Vec::new();
+    let mut id = 0;
+    //石は一手ごとに増えるので，たどった先が巡回することはない
+    while let Some(&next) = tree[id]
+        .childrens
+        .iter()
+        .max_by(|&&a, &&b| tree[a].visit.partial_cmp(&tree[b].visit).unwrap())
+    {
+        let (before, after) = (&tree[id].board.gamebord, &tree[next].board.gamebord);
+        for y in 0..BOARDSIZE {
+            for x in 0..BOARDSIZE {
+                if before[y][x] as i32 == Stone::Blank as i32
+                    && after[y][x] as i32 != Stone::Blank as i32
+                {
+                    pv.push((y, x));
+                }
+            }
+        }
+        id = next;
+    }
+    pv
+}
+fn root_stats(tree: &Vec<Node>) -> Vec<MoveStat> {
+    tree[0]
+        .childrens
+        .iter()
+        .map(|&id| MoveStat {
+            pos: tree[id].moves.unwrap(), //ルートの子は共有されないので，ルートからの手と一致する
+            visits: tree[id].visit as u32,
+            win_rate: tree[id].win / tree[id].visit,
+            score: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_moves_are_for_side_to_move() {
+        //白が一手打った局面のノードは，次に打つ黒の手を持つ
+        let mut board = Board::new(BOARDSIZE, 0, 0);
+        board.count_stone();
+        let mov = super::super::can_set_pos(&board, Stone::White)[0];
+        super::super::put_stone(&mut board, Stone::White, mov);
+        let node = Node::new(mov, &board, Stone::White);
+        assert_eq!(
+            node.unusedmoves,
+            super::super::can_set_pos(&board, Stone::Black)
         );
-    });
-    None
+        for &pos in node.unusedmoves.iter() {
+            assert!(super::super::can_cnt(&board.gamebord, Stone::Black, pos, false).len() != 0);
+        }
+    }
 }
@@ -0,0 +1,416 @@
+use crate::mygame::{Board, Stone, BOARDSIZE};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::OnceLock;
+
+/*
+    パターン評価関数(Logistello/Edax方式)
+    辺・隅・斜め等の形(パターン)ごとに，そのマスの並び(空き/手番側/相手側の3^n通り)に重みを持ち，
+    盤面上の全ての対称な位置の重みを足し合わせて評価値とする
+    重みは進行度(石数)ごとに別に持ち，最終的な石数差を回帰で学習する(tools/train.rs)
+*/
+pub const PATTERN_FILE: &str = "./resources/pattern.bin";
+const MAGIC: &[u8; 4] = b"OTPW";
+const VERSION: u32 = 1;
+const PHASES: usize = 6; //進行度の分割数
+const MAX_NAME_LENGTH: usize = 64; //ファイル中のパターン名の長さの上限(壊れたファイルで巨大な領域を確保しないため)
+
+struct Pattern {
+    name: &'static str,
+    squares: &'static [(usize, usize)], //左上の隅を基準とした形．対称な位置は自動で作成する
+}
+const PATTERNS: [Pattern; 11] = [
+    Pattern {
+        name: "edge2x",
+        squares: &[
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (0, 5),
+            (0, 6),
+            (0, 7),
+            (1, 1),
+            (1, 6),
+        ],
+    },
+    Pattern {
+        name: "corner2x5",
+        squares: &[
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+        ],
+    },
+    Pattern {
+        name: "corner3x3",
+        squares: &[
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+        ],
+    },
+    Pattern {
+        name: "hv2",
+        squares: &[
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (1, 5),
+            (1, 6),
+            (1, 7),
+        ],
+    },
+    Pattern {
+        name: "hv3",
+        squares: &[
+            (2, 0),
+            (2, 1),
+            (2, 2),
+            (2, 3),
+            (2, 4),
+            (2, 5),
+            (2, 6),
+            (2, 7),
+        ],
+    },
+    Pattern {
+        name: "hv4",
+        squares: &[
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (3, 3),
+            (3, 4),
+            (3, 5),
+            (3, 6),
+            (3, 7),
+        ],
+    },
+    Pattern {
+        name: "diag8",
+        squares: &[
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 5),
+            (6, 6),
+            (7, 7),
+        ],
+    },
+    Pattern {
+        name: "diag7",
+        squares: &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)],
+    },
+    Pattern {
+        name: "diag6",
+        squares: &[(0, 2), (1, 3), (2, 4), (3, 5), (4, 6), (5, 7)],
+    },
+    Pattern {
+        name: "diag5",
+        squares: &[(0, 3), (1, 4), (2, 5), (3, 6), (4, 7)],
+    },
+    Pattern {
+        name: "diag4",
+        squares: &[(0, 4), (1, 5), (2, 6), (3, 7)],
+    },
+];
+
+//盤面上に置かれたパターン一つ分
+struct Instance {
+    offset: usize,       //重み配列の中でのこのパターンの開始位置
+    squares: Vec<usize>, //盤面上のマス(y*BOARDSIZE+x)
+}
+
+pub struct PatternEval {
+    weights: Vec<Vec<f32>>, //[進行度][各パターンの重みを連結したもの]
+    bias: Vec<f32>,         //[進行度]
+    instances: Vec<Instance>,
+}
+impl PatternEval {
+    /*
+        全ての重みを0とした評価関数を作成する(学習の初期値)
+    */
+    pub fn new() -> PatternEval {
+        let mut instances = Vec::new();
+        let mut offset = 0;
+        for pattern in PATTERNS.iter() {
+            let mut seen: Vec<Vec<usize>> = Vec::new();
+            for symmetry in 0..8 {
+                let squares: Vec<usize> = pattern
+                    .squares
+                    .iter()
+                    .map(|&pos| {
+                        let (y, x) = transform(pos, symmetry);
+                        y * BOARDSIZE + x
+                    })
+                    .collect();
+                //同じマスの組になる対称形は重複して数えない
+                let mut key = squares.clone();
+                key.sort();
+                if seen.contains(&key) {
+                    continue;
+                }
+                seen.push(key);
+                instances.push(Instance {
+                    offset: offset,
+                    squares: squares,
+                });
+            }
+            offset += pattern_size(pattern);
+        }
+        PatternEval {
+            weights: vec![vec![0.0; offset]; PHASES],
+            bias: vec![0.0; PHASES],
+            instances: instances,
+        }
+    }
+    /*
+        石数から進行度を求める
+    */
+    pub fn phase(board: &Board) -> usize {
+        let discs = (board.black_num + board.white_num) as usize;
+        let discs = std::cmp::max(discs, 4) - 4;
+        std::cmp::min(discs * PHASES / (BOARDSIZE * BOARDSIZE - 3), PHASES - 1)
+    }
+    /*
+        盤面をcolorの視点で見たときの，各パターンの重みの位置を返す
+    */
+    pub fn indices(&self, board: &Board, color: Stone) -> Vec<usize> {
+        self.instances
+            .iter()
+            .map(|instance| {
+                let mut index = 0;
+                for &square in instance.squares.iter().rev() {
+                    let stone = board.gamebord[square / BOARDSIZE][square % BOARDSIZE];
+                    index = index * 3
+                        + match stone {
+                            Stone::Blank => 0,
+                            _ if stone as i32 == color as i32 => 1,
+                            _ => 2,
+                        };
+                }
+                instance.offset + index
+            })
+            .collect()
+    }
+    /*
+        colorから見た盤面の評価値(予想される最終的な石数差)
+        board.black_num, white_numは更新済みであること
+    */
+    pub fn evaluate(&self, board: &Board, color: Stone) -> f32 {
+        let phase = PatternEval::phase(board);
+        let weights = &self.weights[phase];
+        self.bias[phase]
+            + self
+                .indices(board, color)
+                .iter()
+                .map(|&i| weights[i])
+                .sum::<f32>()
+    }
+    /*
+        学習用:indicesで求めた特徴に対して目標値との誤差を小さくする方向へ重みを更新し，更新前の誤差を返す
+    */
+    pub fn train(&mut self, phase: usize, indices: &[usize], target: f32, rate: f32) -> f32 {
+        let weights = &mut self.weights[phase];
+        let predict = self.bias[phase] + indices.iter().map(|&i| weights[i]).sum::<f32>();
+        let error = target - predict;
+        let step = rate * error / (indices.len() + 1) as f32;
+        for &i in indices {
+            weights[i] += step;
+        }
+        self.bias[phase] += step;
+        error
+    }
+    /*
+        重みをファイルへ保存する
+        形式(リトルエンディアン)
+            "OTPW" バージョン(u32) 進行度の数(u32) パターンの数(u32)
+            パターンごとに 名前の長さ(u32) 名前 マスの数(u32)
+            進行度ごとに バイアス(f32) 重み(f32 * 全パターンの3^マス数の合計)
+    */
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        for n in [VERSION, PHASES as u32, PATTERNS.len() as u32].iter() {
+            out.write_all(&n.to_le_bytes())?;
+        }
+        for pattern in PATTERNS.iter() {
+            out.write_all(&(pattern.name.len() as u32).to_le_bytes())?;
+            out.write_all(pattern.name.as_bytes())?;
+            out.write_all(&(pattern.squares.len() as u32).to_le_bytes())?;
+        }
+        for phase in 0..PHASES {
+            out.write_all(&self.bias[phase].to_le_bytes())?;
+            for w in self.weights[phase].iter() {
+                out.write_all(&w.to_le_bytes())?;
+            }
+        }
+        out.flush()
+    }
+    /*
+        saveで保存した重みを読み込む
+        バージョンやパターンの構成が現在のものと異なる場合はエラーとする
+    */
+    pub fn load(path: &str) -> io::Result<PatternEval> {
+        let mut input = BufReader::new(File::open(path)?);
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("パターン評価のファイルではありません"));
+        }
+        if read_u32(&mut input)? != VERSION {
+            return Err(invalid("バージョンが異なります"));
+        }
+        if read_u32(&mut input)? as usize != PHASES
+            || read_u32(&mut input)? as usize != PATTERNS.len()
+        {
+            return Err(invalid("パターンの構成が異なります"));
+        }
+        for pattern in PATTERNS.iter() {
+            let length = read_u32(&mut input)? as usize;
+            if length > MAX_NAME_LENGTH {
+                return Err(invalid("パターンの名前が長すぎます"));
+            }
+            let mut name = vec![0u8; length];
+            input.read_exact(&mut name)?;
+            if name != pattern.name.as_bytes()
+                || read_u32(&mut input)? as usize != pattern.squares.len()
+            {
+                return Err(invalid("パターンの構成が異なります"));
+            }
+        }
+        let mut eval = PatternEval::new();
+        for phase in 0..PHASES {
+            eval.bias[phase] = read_f32(&mut input)?;
+            for w in eval.weights[phase].iter_mut() {
+                *w = read_f32(&mut input)?;
+            }
+        }
+        Ok(eval)
+    }
+}
+
+/*
+    起動時にPATTERN_FILEから読み込んだ評価関数を返す
+    ファイルがない，もしくは読み込めなかった場合はNone
+*/
+static SHARED: OnceLock<Option<PatternEval>> = OnceLock::new();
+pub fn shared() -> Option<&'static PatternEval> {
+    SHARED
+        .get_or_init(|| match PatternEval::load(PATTERN_FILE) {
+            Ok(eval) => Some(eval),
+            Err(e) => {
+                eprintln!(
+                    "パターン評価の重み({})を読み込めないため，評価関数を使用します: {}",
+                    PATTERN_FILE, e
+                );
+                None
+            }
+        })
+        .as_ref()
+}
+/*
+    学習済みの重みがあればパターン評価，なければ評価関数(evaluate.rs)で盤面を評価する
+*/
+pub fn evaluate(board: &Board, color: Stone) -> f32 {
+    match shared() {
+        Some(eval) => eval.evaluate(board, color),
+        None => super::evaluate::evaluate(board, color, &super::evaluate::EvalWeights::default()),
+    }
+}
+
+fn pattern_size(pattern: &Pattern) -> usize {
+    3usize.pow(pattern.squares.len() as u32)
+}
+/*
+    盤面の8つの対称変換(回転・反転)
+*/
+pub fn transform(pos: (usize, usize), symmetry: usize) -> (usize, usize) {
+    let n = BOARDSIZE - 1;
+    let (y, x) = pos;
+    match symmetry {
+        0 => (y, x),
+        1 => (x, n - y),
+        2 => (n - y, n - x),
+        3 => (n - x, y),
+        4 => (y, n - x),
+        5 => (n - y, x),
+        6 => (x, y),
+        _ => (n - x, n - y),
+    }
+}
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+fn read_f32(input: &mut impl Read) -> io::Result<f32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(f32::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //テストごとに別の一時ファイル
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("othello-{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut eval = PatternEval::new();
+        for phase in 0..PHASES {
+            eval.bias[phase] = phase as f32 * 0.5;
+            for (i, w) in eval.weights[phase].iter_mut().enumerate().step_by(97) {
+                *w = (i % 13) as f32 - 6.0;
+            }
+        }
+        let path = temp_path("round_trip.bin");
+        eval.save(&path).unwrap();
+        let loaded = PatternEval::load(&path);
+        let _ = std::fs::remove_file(&path);
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.bias, eval.bias);
+        assert!(loaded.weights == eval.weights);
+    }
+    #[test]
+    fn huge_name_length_is_rejected() {
+        let path = temp_path("huge_name.bin");
+        let mut data = MAGIC.to_vec();
+        for n in [VERSION, PHASES as u32, PATTERNS.len() as u32, u32::MAX].iter() {
+            data.extend_from_slice(&n.to_le_bytes());
+        }
+        std::fs::write(&path, data).unwrap();
+        let loaded = PatternEval::load(&path);
+        let _ = std::fs::remove_file(&path);
+        match loaded {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            Ok(_) => panic!("壊れたファイルが読み込めました"),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use super::montecarlo::MoveStat;
+use crate::mygame::Board;
+use std::time::Duration;
+
+/*
+    一回の思考の統計
+    どの方法で着手を決めても同じ形で返し，GUI・ツール・エンジンモードで表示する
+    エンジンモードではinfo行として出力し，外部エンジンのinfo行もこの形で読み込む
+        info method <方法> nodes <N> iterations <N> time <ミリ秒> depth <N> [score <石数差>] [pv <d3等の座標..>]
+*/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchMethod {
+    Random,     //わざと選んだランダムな手(EngineConfig::mistake)
+    Book,       //定石
+    Endgame,    //完全読み
+    MonteCarlo, //モンテカルロ木探索
+    External,   //外部エンジン
+}
+impl SearchMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchMethod::Random => "random",
+            SearchMethod::Book => "book",
+            SearchMethod::Endgame => "endgame",
+            SearchMethod::MonteCarlo => "mcts",
+            SearchMethod::External => "external",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<SearchMethod> {
+        [
+            SearchMethod::Random,
+            SearchMethod::Book,
+            SearchMethod::Endgame,
+            SearchMethod::MonteCarlo,
+            SearchMethod::External,
+        ]
+        .iter()
+        .find(|m| m.name() == name)
+        .copied()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchStats {
+    pub method: SearchMethod,
+    pub best: Option<(usize, usize)>, //選んだ手(None:置ける場所なし)
+    pub nodes: usize,                 //調べた局面の数
+    pub iterations: usize,            //モンテカルロ木探索の試行回数
+    pub elapsed: Duration,
+    pub max_depth: usize,        //読んだ最大の深さ(手数)
+    pub score: Option<i32>,      //完全読みで求めた手番側から見た石数差
+    pub pv: Vec<(usize, usize)>, //読み筋(最善と考えた手順．パスは含まない)
+    pub children: Vec<MoveStat>, //ルートの候補手ごとの統計
+}
+impl SearchStats {
+    pub fn new(method: SearchMethod) -> SearchStats {
+        SearchStats {
+            method: method,
+            best: None,
+            nodes: 0,
+            iterations: 0,
+            elapsed: Duration::from_secs(0),
+            max_depth: 0,
+            score: None,
+            pv: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+    //1秒あたりに調べた局面の数
+    pub fn nps(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        self.nodes as f64 / secs
+    }
+    pub fn pv_text(&self) -> String {
+        self.pv
+            .iter()
+            .map(|&pos| Board::pos_to_notation(pos))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+    //エンジンモードで出力するinfo行
+    pub fn to_info(&self) -> String {
+        let mut line = format!(
+            "info method {} nodes {} iterations {} time {} depth {}",
+            self.method.name(),
+            self.nodes,
+            self.iterations,
+            self.elapsed.as_millis(),
+            self.max_depth
+        );
+        if let Some(score) = self.score {
+            line += &format!(" score {}", score);
+        }
+        if self.pv.len() != 0 {
+            line += &format!(" pv {}", self.pv_text());
+        }
+        line
+    }
+    /*
+        外部エンジンのinfo行を読み込む．知らない項目は無視する
+        methodがない場合はExternalとする
+    */
+    pub fn parse_info(line: &str) -> Option<SearchStats> {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("info") {
+            return None;
+        }
+        let mut stats = SearchStats::new(SearchMethod::External);
+        let words: Vec<&str> = words.collect();
+        let mut i = 0;
+        while i < words.len() {
+            let value = words.get(i + 1).copied().unwrap_or("");
+            match words[i] {
+                "method" => {
+                    stats.method = SearchMethod::from_name(value).unwrap_or(SearchMethod::External)
+                }
+                "nodes" => stats.nodes = value.parse().ok()?,
+                "iterations" => stats.iterations = value.parse().ok()?,
+                "time" => stats.elapsed = Duration::from_millis(value.parse().ok()?),
+                "depth" => stats.max_depth = value.parse().ok()?,
+                "score" => stats.score = Some(value.parse().ok()?),
+                "pv" => {
+                    //pvは行の最後に置く
+                    stats.pv = words[i + 1..]
+                        .iter()
+                        .filter_map(|w| Board::notation_to_pos(w))
+                        .collect();
+                    break;
+                }
+                _ => {}
+            }
+            i += 2;
+        }
+        stats.best = stats.pv.get(0).copied();
+        Some(stats)
+    }
+}
+impl std::fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}局面 {}.{:03}秒 ({:.0}局面/秒) 深さ{}",
+            self.method.name(),
+            self.nodes,
+            self.elapsed.as_secs(),
+            self.elapsed.subsec_millis(),
+            self.nps(),
+            self.max_depth
+        )?;
+        if self.iterations != 0 {
+            write!(f, " 試行{}回", self.iterations)?;
+        }
+        if let Some(score) = self.score {
+            write!(f, " 石数差{:+}", score)?;
+        }
+        if self.pv.len() != 0 {
+            write!(f, " 読み筋 {}", self.pv_text())?;
+        }
+        Ok(())
+    }
+}
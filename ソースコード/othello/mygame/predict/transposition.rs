@@ -0,0 +1,130 @@
+/*
+    置換表(トランスポジションテーブル)
+    手順が違っても同じ局面になった場合に探索結果を使い回すため，Zobristハッシュ(Board::hash)をキーに
+    評価値とその種類(上界・下界・正確な値)，読んだ深さ，最善手を保存する
+    大きさは固定で，キーの下位ビットで決まる一か所にだけ保存する
+*/
+pub const TABLE_BITS: u32 = 18; //既定の大きさ(2^18個)
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact, //正確な値
+    Lower, //βカットした(実際の値はこれ以上)
+    Upper, //どの手もαを超えなかった(実際の値はこれ以下)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub key: u64,
+    pub value: f32,
+    pub depth: u8,                    //この局面から読んだ深さ
+    pub bound: Bound,                 //valueの種類
+    pub best: Option<(usize, usize)>, //最善手(パスの場合はNone)
+    generation: u8,                   //保存したときの探索の世代
+}
+impl Entry {
+    /*
+        [alpha,beta]の窓で探索する場合に，この値をそのまま探索結果として使えるか
+    */
+    pub fn cutoff(&self, alpha: f32, beta: f32) -> bool {
+        match self.bound {
+            Bound::Exact => true,
+            Bound::Lower => self.value >= beta,
+            Bound::Upper => self.value <= alpha,
+        }
+    }
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    generation: u8,
+}
+impl TranspositionTable {
+    pub fn new(bits: u32) -> TranspositionTable {
+        TranspositionTable {
+            entries: vec![None; 1 << bits],
+            generation: 0,
+        }
+    }
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+    /*
+        新しい探索を始める際に呼ぶ
+        以前の探索で保存したものは，深さに関わらず上書きされやすくなる
+    */
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        match self.entries[self.index(key)] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+    /*
+        保存先が空いている，同じ局面，以前の探索のもの，もしくは今回より浅い探索の結果であれば置き換える
+    */
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u8,
+        value: f32,
+        bound: Bound,
+        best: Option<(usize, usize)>,
+    ) {
+        let index = self.index(key);
+        let replace = match self.entries[index] {
+            None => true,
+            Some(old) => old.key == key || old.generation != self.generation || old.depth <= depth,
+        };
+        if replace {
+            self.entries[index] = Some(Entry {
+                key: key,
+                value: value,
+                depth: depth,
+                bound: bound,
+                best: best,
+                generation: self.generation,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BITS: u32 = 4;
+    const KEY: u64 = 3;
+    const OTHER: u64 = KEY + (1 << BITS); //KEYと同じ場所に保存される別の局面
+
+    #[test]
+    fn deeper_entry_is_kept_within_a_search() {
+        let mut table = TranspositionTable::new(BITS);
+        table.store(KEY, 5, 1.0, Bound::Exact, None);
+        table.store(OTHER, 2, 2.0, Bound::Exact, None);
+        assert_eq!(table.probe(KEY).map(|e| e.depth), Some(5));
+        assert!(table.probe(OTHER).is_none());
+    }
+    #[test]
+    fn entry_from_previous_search_is_replaced() {
+        let mut table = TranspositionTable::new(BITS);
+        table.store(KEY, 5, 1.0, Bound::Exact, None);
+        table.new_search();
+        table.store(OTHER, 2, 2.0, Bound::Exact, None);
+        assert!(table.probe(KEY).is_none());
+        assert_eq!(table.probe(OTHER).map(|e| e.depth), Some(2));
+    }
+    #[test]
+    fn same_position_is_overwritten() {
+        let mut table = TranspositionTable::new(BITS);
+        table.store(KEY, 5, 1.0, Bound::Exact, None);
+        table.store(KEY, 2, 3.0, Bound::Lower, Some((2, 3)));
+        let entry = table.probe(KEY).unwrap();
+        assert_eq!(
+            (entry.depth, entry.bound, entry.best),
+            (2, Bound::Lower, Some((2, 3)))
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use crate::mygame::predict::transposition::{self, TranspositionTable};
+use crate::mygame::predict::{self, endgame, montecarlo::MoveStat};
+use crate::mygame::record::StartPosition;
+use crate::mygame::{Board, Stone, BOARDSIZE};
+use ggez::{graphics, Context, GameResult};
+use glam::*;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+/*
+    詰めオセロ(終盤の次の一手)
+    PUZZLE_FILEの局面を順に出題し，プレイヤーが打った手を完全読みで判定する
+    正解は問題に書かず，最終的な石数差が最善の手と同じになる手を全て正解とする
+    各問題を解いたか・失敗したかをPUZZLE_RESULT_FILEに保存し，次回の起動時にも引き継ぐ
+*/
+pub const PUZZLE_FILE: &str = "./resources/puzzles.txt";
+pub const PUZZLE_RESULT_FILE: &str = "./puzzle_results.txt";
+const PUZZLE_MAX_EMPTIES: usize = 16; //完全読みに時間がかかりすぎないよう，これより空きの多い問題は読み込まない
+
+pub struct Puzzle {
+    pub start: StartPosition,
+    pub title: String,
+}
+impl Puzzle {
+    /*
+        "<盤面64文字> <X|O> <問題文>"の形式の1行を読み込む
+        空行と#で始まる行はNone
+    */
+    fn parse(line: &str) -> Option<Result<Puzzle, String>> {
+        let line = line.trim();
+        if line.len() == 0 || line.starts_with('#') {
+            return None;
+        }
+        let mut words = line.splitn(3, char::is_whitespace);
+        let (board, side) = (words.next()?, words.next().unwrap_or(""));
+        let start = match StartPosition::parse_word(&format!("start={}:{}", board, side)) {
+            Some(start) => start,
+            None => return Some(Err(format!("問題を解釈できません: {}", line))),
+        };
+        let empties = start.board.to_text().matches('-').count();
+        if empties > PUZZLE_MAX_EMPTIES {
+            return Some(Err(format!(
+                "空きマスが{}より多い問題です: {}",
+                PUZZLE_MAX_EMPTIES, line
+            )));
+        }
+        if predict::can_set_pos(&start.board, start.color).len() == 0 {
+            return Some(Err(format!("手番側が置けない問題です: {}", line)));
+        }
+        Some(Ok(Puzzle {
+            start: start,
+            title: words.next().unwrap_or("").trim().to_string(),
+        }))
+    }
+    //結果を保存するときの問題の名前(局面と手番)
+    fn key(&self) -> String {
+        self.start.to_word()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PuzzleResult {
+    Solved,
+    Failed,
+}
+impl PuzzleResult {
+    fn name(&self) -> &'static str {
+        match self {
+            PuzzleResult::Solved => "solved",
+            PuzzleResult::Failed => "failed",
+        }
+    }
+}
+
+//プレイヤーの解答と判定
+pub struct Answer {
+    pub pos: (usize, usize),
+    pub score: i32,                //打った手の石数差(手番側から見た値)
+    pub best_score: i32,           //最善の石数差
+    pub best: Vec<(usize, usize)>, //最善の石数差になる手
+}
+impl Answer {
+    pub fn correct(&self) -> bool {
+        self.score == self.best_score
+    }
+}
+
+pub struct PuzzleMode {
+    puzzles: Vec<Puzzle>,
+    pub index: usize,
+    results: HashMap<String, PuzzleResult>,
+    solution: Option<Vec<MoveStat>>, //完全読みで求めた候補手ごとの石数差
+    receiver: Option<Receiver<Vec<MoveStat>>>,
+    stop: Arc<AtomicBool>, //出題中の問題の完全読みの停止フラグ
+    pub answer: Option<Answer>,
+}
+impl PuzzleMode {
+    //問題と，これまでの結果を読み込み最初の問題を出す
+    pub fn load() -> Result<PuzzleMode, String> {
+        let text = std::fs::read_to_string(PUZZLE_FILE)
+            .map_err(|e| format!("{}を開けません: {}", PUZZLE_FILE, e))?;
+        let mut puzzles = Vec::new();
+        for puzzle in text.lines().filter_map(Puzzle::parse) {
+            match puzzle {
+                Ok(puzzle) => puzzles.push(puzzle),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        if puzzles.len() == 0 {
+            return Err(format!("{}に問題がありません", PUZZLE_FILE));
+        }
+        let mut mode = PuzzleMode {
+            puzzles: puzzles,
+            index: 0,
+            results: load_results(),
+            solution: None,
+            receiver: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            answer: None,
+        };
+        //まだ解いていない最初の問題から始める
+        mode.index = (0..mode.puzzles.len())
+            .find(|&i| mode.result(i) != Some(PuzzleResult::Solved))
+            .unwrap_or(0);
+        mode.select(mode.index as i32);
+        Ok(mode)
+    }
+    pub fn current(&self) -> &Puzzle {
+        &self.puzzles[self.index]
+    }
+    fn result(&self, index: usize) -> Option<PuzzleResult> {
+        self.results.get(&self.puzzles[index].key()).copied()
+    }
+    /*
+        index番目の問題を出し，別スレッドで完全読みを始める
+        前の問題の完全読みが終わっていなければ止める
+        範囲外のindexは反対側へ回り込む
+    */
+    pub fn select(&mut self, index: i32) {
+        let len = self.puzzles.len() as i32;
+        self.index = index.rem_euclid(len) as usize;
+        self.answer = None;
+        self.solution = None;
+        let start = self.current().start;
+        self.stop.store(true, Ordering::Relaxed);
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = stop.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut table = TranspositionTable::new(transposition::TABLE_BITS);
+            if let Some(stats) = endgame::search_until(&start.board, start.color, &mut table, &stop)
+            {
+                let _ = sender.send(stats.children);
+            }
+        });
+        self.receiver = Some(receiver);
+    }
+    //毎フレーム呼ぶ．完全読みが終わっていれば結果を受け取る
+    pub fn update(&mut self) {
+        if let Some(receiver) = &self.receiver {
+            if let Ok(children) = receiver.try_recv() {
+                self.solution = Some(children);
+                self.receiver = None;
+            }
+        }
+    }
+    /*
+        posに打った解答を判定し，結果を保存する
+        完全読みが終わっていない，既に解答した，置けない場所の場合はNone
+    */
+    pub fn answer(&mut self, pos: (usize, usize)) -> Option<&Answer> {
+        if self.answer.is_some() {
+            return None;
+        }
+        let solution = self.solution.as_ref()?;
+        let score = solution.iter().find(|m| m.pos == pos)?.score?;
+        let best_score = solution.iter().filter_map(|m| m.score).max()?;
+        let best = solution
+            .iter()
+            .filter(|m| m.score == Some(best_score))
+            .map(|m| m.pos)
+            .collect();
+        let answer = Answer {
+            pos: pos,
+            score: score,
+            best_score: best_score,
+            best: best,
+        };
+        //一度解いた問題は，後で失敗しても解いたままにする
+        let key = self.current().key();
+        if answer.correct() {
+            self.results.insert(key, PuzzleResult::Solved);
+        } else if self.results.get(&key) != Some(&PuzzleResult::Solved) {
+            self.results.insert(key, PuzzleResult::Failed);
+        }
+        self.save_results();
+        self.answer = Some(answer);
+        self.answer.as_ref()
+    }
+    fn save_results(&self) {
+        let result = std::fs::File::create(PUZZLE_RESULT_FILE).and_then(|mut file| {
+            for (key, result) in self.results.iter() {
+                writeln!(file, "{} {}", key, result.name())?;
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            eprintln!("{}へ保存できません: {}", PUZZLE_RESULT_FILE, e);
+        }
+    }
+    /*
+        問題文・これまでの成績・判定を盤面の上側に描画し，解答後は最善の手を緑の円で示す
+    */
+    pub fn draw(&self, ctx: &mut Context, font: &graphics::Font) -> GameResult<()> {
+        let count = |result: PuzzleResult| {
+            (0..self.puzzles.len())
+                .filter(|&i| self.result(i) == Some(result))
+                .count()
+        };
+        let side = if self.current().start.color as i32 == Stone::White as i32 {
+            "白番"
+        } else {
+            "黒番"
+        };
+        let mark = match self.result(self.index) {
+            Some(PuzzleResult::Solved) => " [解いた]",
+            Some(PuzzleResult::Failed) => " [失敗]",
+            None => "",
+        };
+        let mut text = format!(
+            "問題 {}/{}{}  解いた {} 失敗 {}\n{} {}",
+            self.index + 1,
+            self.puzzles.len(),
+            mark,
+            count(PuzzleResult::Solved),
+            count(PuzzleResult::Failed),
+            side,
+            self.current().title
+        );
+        let mut color = graphics::Color::WHITE;
+        match &self.answer {
+            Some(answer) => {
+                let best: Vec<String> = answer
+                    .best
+                    .iter()
+                    .map(|&p| Board::pos_to_notation(p))
+                    .collect();
+                if answer.correct() {
+                    text += &format!(
+                        "\n正解! {} 石数差{:+}",
+                        Board::pos_to_notation(answer.pos),
+                        answer.score
+                    );
+                    color = graphics::Color::new(0.5, 1.0, 0.5, 1.0);
+                } else {
+                    text += &format!(
+                        "\n不正解 {} 石数差{:+} (最善 {} {:+})",
+                        Board::pos_to_notation(answer.pos),
+                        answer.score,
+                        best.join(" "),
+                        answer.best_score
+                    );
+                    color = graphics::Color::new(1.0, 0.5, 0.5, 1.0);
+                }
+                text += "  盤面をクリックでもう一度";
+            }
+            None if self.solution.is_none() => text += "\n完全読み中...",
+            None => {}
+        }
+        super::draw_banner(ctx, font, &text, color)?;
+        if let Some(answer) = &self.answer {
+            let cell_size = crate::WIDTH / BOARDSIZE as f32;
+            for &pos in answer.best.iter() {
+                let ring = graphics::Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::stroke(4.0),
+                    Vec2::new(0.0, 0.0),
+                    cell_size / 2.3,
+                    0.1,
+                    graphics::Color::new(0.2, 1.0, 0.4, 1.0),
+                )?;
+                let center = Vec2::new(
+                    pos.1 as f32 * cell_size + cell_size / 2.0,
+                    pos.0 as f32 * cell_size + cell_size / 2.0,
+                );
+                graphics::draw(ctx, &ring, (center,))?;
+            }
+        }
+        Ok(())
+    }
+}
+//詰めオセロをやめたときに，完全読みが終わっていなければ止める
+impl Drop for PuzzleMode {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/*
+    PUZZLE_RESULT_FILEの"<問題の名前> <solved|failed>"の行を読み込む
+    ファイルがない場合や読めない行は結果なしとする
+*/
+fn load_results() -> HashMap<String, PuzzleResult> {
+    let mut results = HashMap::new();
+    let text = match std::fs::read_to_string(PUZZLE_RESULT_FILE) {
+        Ok(text) => text,
+        Err(_) => return results,
+    };
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        let (key, result) = (words.next(), words.next());
+        let result = match result {
+            Some("solved") => PuzzleResult::Solved,
+            Some("failed") => PuzzleResult::Failed,
+            _ => continue,
+        };
+        if let Some(key) = key {
+            results.insert(key.to_string(), result);
+        }
+    }
+    results
+}
@@ -0,0 +1,27 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/*
+    ゲームで使う乱数
+    CPUの思考(展開する手・プレイアウト・定石の選択)とパーティクルの乱数は，全て対局ごとのシードから作る
+    一手ごとの乱数はシードとそれまでの手数から作るので，思考時間やスレッドの実行順に左右されず，
+    同じシードと同じ棋譜であれば同じ手を打つ
+*/
+pub type GameRng = SmallRng; //プレイアウト等で大量に使うため，高速な疑似乱数を使う
+
+//新しい対局のシードを決める
+pub fn new_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+pub fn from_seed(seed: u64) -> GameRng {
+    SmallRng::seed_from_u64(seed)
+}
+/*
+    シードとstream(手数等)から，それぞれ独立した乱数列のためのシードを作る(SplitMix64)
+*/
+pub fn derive(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
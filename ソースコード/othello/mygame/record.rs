@@ -0,0 +1,190 @@
+use crate::mygame::{board::Board, gametree::GameTree, predict, Stone, BOARDSIZE};
+
+/*
+    棋譜の読み書き
+    棋譜は"f5d6c3"のように座標を続けて書いたもの
+    パスは書かず，手番側に置ける場所がない場合に自動で補う
+*/
+pub fn parse_transcript(text: &str) -> Option<Vec<(usize, usize)>> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if text.len() % 2 != 0 || !text.is_ascii() {
+        return None;
+    }
+    let mut ret = Vec::with_capacity(text.len() / 2);
+    for i in (0..text.len()).step_by(2) {
+        ret.push(Board::notation_to_pos(&text[i..i + 2])?);
+    }
+    Some(ret)
+}
+pub fn to_transcript(moves: &[(usize, usize)]) -> String {
+    moves
+        .iter()
+        .map(|pos| Board::pos_to_notation(*pos))
+        .collect::<Vec<String>>()
+        .join("")
+}
+/*
+    初期盤面から棋譜通りに打ち進めた盤面と，次の手番を返す
+    このゲームは白が先手だが，一般的な棋譜は黒が先手のため，最初の手を置ける色を先手とする
+    (初期盤面で白と黒が置ける場所は重ならない)
+    None:途中で置けない手があった
+*/
+pub fn replay(moves: &[(usize, usize)], width: u32, height: u32) -> Option<(Board, Stone)> {
+    replay_from(None, moves, width, height)
+}
+/*
+    startの局面(Noneの場合は初期盤面)から棋譜通りに打ち進めた盤面と，次の手番を返す
+*/
+pub fn replay_from(
+    start: Option<StartPosition>,
+    moves: &[(usize, usize)],
+    width: u32,
+    height: u32,
+) -> Option<(Board, Stone)> {
+    let (mut board, mut color) = match start {
+        Some(start) => (
+            Board::from_text(&start.board.to_text(), width, height)?,
+            start.color,
+        ),
+        None => {
+            let board = Board::new(BOARDSIZE, width, height);
+            let mut color = Stone::White;
+            if let Some(first) = moves.get(0) {
+                if !predict::can_set_pos(&board, Stone::White).contains(first) {
+                    color = Stone::Black;
+                }
+            }
+            (board, color)
+        }
+    };
+    board.count_stone();
+    for pos in moves {
+        if predict::can_set_pos(&board, color).len() == 0 {
+            color.reversestone(); //パス
+        }
+        if !predict::put_stone(&mut board, color, *pos) {
+            return None;
+        }
+        color.reversestone();
+    }
+    if predict::can_set_pos(&board, color).len() == 0
+        && predict::can_set_pos(&board, color.return_reverse_color()).len() != 0
+    {
+        color.reversestone(); //パス
+    }
+    Some((board, color))
+}
+
+/*
+    初期盤面以外から始める対局の最初の局面(盤面編集で作った局面)
+    記録には"start=<Board::to_textの形式の盤面>:<手番 X:黒 O:白>"の形で書く
+*/
+#[derive(Clone, Copy)]
+pub struct StartPosition {
+    pub board: Board,
+    pub color: Stone,
+}
+impl StartPosition {
+    pub fn to_word(&self) -> String {
+        let side = match self.color {
+            Stone::Black => "X",
+            _ => "O",
+        };
+        format!("start={}:{}", self.board.to_text(), side)
+    }
+    pub fn parse_word(word: &str) -> Option<StartPosition> {
+        let mut parts = word.strip_prefix("start=")?.split(':');
+        let board = Board::from_text(parts.next()?, 0, 0)?;
+        let color = match parts.next()? {
+            "X" => Stone::Black,
+            "O" => Stone::White,
+            _ => return None,
+        };
+        Some(StartPosition {
+            board: board,
+            color: color,
+        })
+    }
+}
+
+/*
+    対局の記録
+    1行に1局，"<シード> <棋譜> [エンジン設定] [hints=ヒントの回数] [start=最初の局面]"の形式でGAME_RECORD_FILEへ追記する
+    棋譜は変化を含めてGameTree::to_textの形式で書く(変化がなければparse_transcriptの形式と同じ)
+    エンジン設定はEngineConfig::parseの形式("level=hard"等)
+    組み込みのCPUの手はシードと棋譜とエンジン設定から再現できる(tools/replay.rs)
+*/
+pub const GAME_RECORD_FILE: &str = "./records.txt";
+pub struct GameRecord {
+    pub seed: u64,
+    pub tree: GameTree,
+    pub config: Option<String>,
+    pub hints: u32,
+}
+impl GameRecord {
+    pub fn to_line(&self) -> String {
+        let mut line = format!("{} {}", self.seed, self.tree.to_text());
+        if let Some(config) = &self.config {
+            line += &format!(" {}", config);
+        }
+        if self.hints != 0 {
+            line += &format!(" hints={}", self.hints);
+        }
+        if let Some(start) = &self.tree.start {
+            line += &format!(" {}", start.to_word());
+        }
+        line
+    }
+    pub fn parse(line: &str) -> Option<GameRecord> {
+        let mut words = line.split_whitespace();
+        let seed = words.next()?.parse().ok()?;
+        let (mut transcript, mut config, mut hints) = (String::new(), None, 0);
+        let mut start = None;
+        for word in words {
+            if word.starts_with("hints=") {
+                hints = word[6..].parse().ok()?;
+            } else if word.starts_with("start=") {
+                start = Some(StartPosition::parse_word(word)?);
+            } else if word.contains('=') {
+                config = Some(word.to_string());
+            } else {
+                transcript += word;
+            }
+        }
+        let mut tree = GameTree::parse(&transcript)?;
+        tree.start = start;
+        Some(GameRecord {
+            seed: seed,
+            tree: tree,
+            config: config,
+            hints: hints,
+        })
+    }
+    //本線の手
+    pub fn moves(&self) -> Vec<(usize, usize)> {
+        self.tree.main_line()
+    }
+    //GAME_RECORD_FILEの記録を古い順に全て読み込む(読めない行は飛ばす)
+    pub fn load_all() -> std::io::Result<Vec<GameRecord>> {
+        let text = std::fs::read_to_string(GAME_RECORD_FILE)?;
+        Ok(text.lines().filter_map(GameRecord::parse).collect())
+    }
+    //GAME_RECORD_FILEの最も新しい記録を読み込む
+    pub fn load_latest() -> Result<GameRecord, String> {
+        let records = GameRecord::load_all()
+            .map_err(|e| format!("{}を開けません: {}", GAME_RECORD_FILE, e))?;
+        records
+            .into_iter()
+            .last()
+            .ok_or_else(|| format!("{}に対局の記録がありません", GAME_RECORD_FILE))
+    }
+    //GAME_RECORD_FILEへ追記する
+    pub fn save(&self) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(GAME_RECORD_FILE)?;
+        writeln!(file, "{}", self.to_line())
+    }
+}
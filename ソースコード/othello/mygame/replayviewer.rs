@@ -0,0 +1,79 @@
+use crate::mygame::record::GameRecord;
+use crate::mygame::{predict, Board, Stone};
+
+/*
+    保存した対局(record::GAME_RECORD_FILE)の本線の再生
+    盤面に一手ずつ適用していく．石の配置・回転の演出は呼び出し側(MyGame)が対局中と同じ処理で行う
+    再生中はプレイヤーは石を置けない
+*/
+const AUTOPLAY_INTERVAL: f32 = 1.0; //自動再生で次の手を打つまでの間隔(秒)．石の回転が終わってから数える
+
+pub struct ReplayViewer {
+    pub record: GameRecord,
+    pub moves: Vec<(usize, usize)>, //再生する本線の手
+    pub index: usize,               //盤面に適用済みの手数
+    pub autoplay: bool,
+    timer: f32,
+}
+impl ReplayViewer {
+    //最も新しい記録を読み込む
+    pub fn load_latest() -> Result<ReplayViewer, String> {
+        let record = GameRecord::load_latest()?;
+        Ok(ReplayViewer {
+            moves: record.moves(),
+            record: record,
+            index: 0,
+            autoplay: false,
+            timer: 0.0,
+        })
+    }
+    //index手目までを適用した盤面と次の手番
+    pub fn position(&self, index: usize, width: u32, height: u32) -> Option<(Board, Stone)> {
+        self.record.tree.replay(&self.moves[..index], width, height)
+    }
+    /*
+        次の手(打つ色,位置)を返して手数を進める
+        最後まで進んでいる場合はNone
+    */
+    pub fn next_move(&mut self) -> Option<(Stone, (usize, usize))> {
+        let pos = *self.moves.get(self.index)?;
+        let (board, color) = self.position(self.index, 0, 0)?;
+        //record::replayは次の手番がパスの場合を補っているが，念のため置けるかを確かめる
+        if predict::can_cnt(&board.gamebord, color, pos, false).len() == 0 {
+            return None;
+        }
+        self.index += 1;
+        Some((color, pos))
+    }
+    //手数を一手戻す．最初の局面の場合はfalse
+    pub fn back(&mut self) -> bool {
+        if self.index == 0 {
+            return false;
+        }
+        self.index -= 1;
+        self.timer = 0.0;
+        true
+    }
+    pub fn is_end(&self) -> bool {
+        self.index >= self.moves.len()
+    }
+    /*
+        自動再生の時間を進め，次の手を打つ時になったらtrueを返す
+        石が回転中の間は時間を進めない．最後まで再生したら自動再生を止める
+    */
+    pub fn update(&mut self, delta: f32, rotating: bool) -> bool {
+        if !self.autoplay || rotating {
+            return false;
+        }
+        if self.is_end() {
+            self.autoplay = false;
+            return false;
+        }
+        self.timer += delta;
+        if self.timer < AUTOPLAY_INTERVAL {
+            return false;
+        }
+        self.timer = 0.0;
+        true
+    }
+}
@@ -0,0 +1,174 @@
+use crate::mygame::evalgraph::PositionEval;
+use crate::mygame::Stone;
+use ggez::{graphics, Context, GameResult};
+use glam::*;
+
+/*
+    対局後の振り返り
+    評価値のグラフ(evalgraph.rs)で求めた各局面の勝率から，指定した色の各手で失った勝率を求め，
+    失った勝率に応じて緩手・悪手・大悪手と判定する
+    失った勝率は，手を打つ前の局面の探索で最善とした手の勝率と，同じ探索での打った手の勝率の差とする
+    (前後の局面を別々に探索した勝率の差では，探索のばらつきだけで悪手と判定されることがある)
+*/
+const INACCURACY_LOSS: f32 = 0.1; //緩手とする失った勝率
+const MISTAKE_LOSS: f32 = 0.2; //悪手とする失った勝率
+const BLUNDER_LOSS: f32 = 0.35; //大悪手とする失った勝率
+const REVIEW_FONT_SIZE: f32 = 14.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+impl Severity {
+    pub fn from_loss(loss: f32) -> Option<Severity> {
+        if loss >= BLUNDER_LOSS {
+            Some(Severity::Blunder)
+        } else if loss >= MISTAKE_LOSS {
+            Some(Severity::Mistake)
+        } else if loss >= INACCURACY_LOSS {
+            Some(Severity::Inaccuracy)
+        } else {
+            None
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Inaccuracy => "緩手",
+            Severity::Mistake => "悪手",
+            Severity::Blunder => "大悪手",
+        }
+    }
+    pub fn color(&self) -> graphics::Color {
+        match self {
+            Severity::Inaccuracy => graphics::Color::new(1.0, 0.9, 0.2, 1.0),
+            Severity::Mistake => graphics::Color::new(1.0, 0.55, 0.1, 1.0),
+            Severity::Blunder => graphics::Color::new(1.0, 0.15, 0.15, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReviewedMove {
+    pub index: usize, //棋譜の何番目の手か(0から)
+    pub pos: (usize, usize),
+    pub best: Option<(usize, usize)>, //打つ前の局面で最善とした手
+    pub loss: f32,                    //この手で失った勝率(0〜1)
+    pub severity: Option<Severity>,
+}
+
+/*
+    棋譜のうちcolorが打った手を振り返る
+    evalsは棋譜の各局面の評価(初期局面から順)で，打つ前の局面の評価が求まっている手のみ返す
+*/
+pub fn review(moves: &[(usize, usize)], evals: &[PositionEval], color: Stone) -> Vec<ReviewedMove> {
+    let mut ret = Vec::new();
+    for i in 0..moves.len() {
+        if i >= evals.len() {
+            break;
+        }
+        if evals[i].color as i32 != color as i32 {
+            continue;
+        }
+        //勝率は手番側(color)から見た値
+        let children = &evals[i].children;
+        let best = children.iter().find(|m| Some(m.pos) == evals[i].best);
+        let played = children.iter().find(|m| m.pos == moves[i]);
+        let loss = match (best, played) {
+            (Some(best), Some(played)) => (best.win_rate - played.win_rate).max(0.0),
+            _ => continue,
+        };
+        ret.push(ReviewedMove {
+            index: i,
+            pos: moves[i],
+            best: evals[i].best,
+            loss: loss,
+            severity: Severity::from_loss(loss),
+        });
+    }
+    ret
+}
+
+/*
+    振り返りの要約
+    判定ごとの手数と，一手あたりの平均の失った勝率
+*/
+pub fn summary(reviewed: &[ReviewedMove]) -> String {
+    let count = |severity: Severity| {
+        reviewed
+            .iter()
+            .filter(|m| m.severity == Some(severity))
+            .count()
+    };
+    let average = if reviewed.len() == 0 {
+        0.0
+    } else {
+        reviewed.iter().map(|m| m.loss).sum::<f32>() / reviewed.len() as f32
+    };
+    format!(
+        "{} {}  {} {}  {} {}  平均損失 {:.1}%",
+        Severity::Blunder.name(),
+        count(Severity::Blunder),
+        Severity::Mistake.name(),
+        count(Severity::Mistake),
+        Severity::Inaccuracy.name(),
+        count(Severity::Inaccuracy),
+        average * 100.0
+    )
+}
+
+/*
+    振り返った手を盤面に描画する
+    打った手を判定の色の円で囲み，より良い手を緑の円と"Best"の文字で示す
+    より良い手のマスは打つ前に空いていて，打った後も空いたままなので，打った後の盤面に重ねてよい
+*/
+pub fn draw_move(
+    ctx: &mut Context,
+    font: &graphics::Font,
+    reviewed: &ReviewedMove,
+) -> GameResult<()> {
+    let cell_size = crate::WIDTH / crate::mygame::BOARDSIZE as f32;
+    let center = |pos: (usize, usize)| {
+        Vec2::new(
+            pos.1 as f32 * cell_size + cell_size / 2.0,
+            pos.0 as f32 * cell_size + cell_size / 2.0,
+        )
+    };
+    if let Some(severity) = reviewed.severity {
+        let ring = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::stroke(4.0),
+            Vec2::new(0.0, 0.0),
+            cell_size / 2.3,
+            0.1,
+            severity.color(),
+        )?;
+        graphics::draw(ctx, &ring, (center(reviewed.pos),))?;
+    }
+    match reviewed.best {
+        Some(best) if best != reviewed.pos && reviewed.severity.is_some() => {
+            let ring = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::stroke(4.0),
+                Vec2::new(0.0, 0.0),
+                cell_size / 2.3,
+                0.1,
+                graphics::Color::new(0.2, 1.0, 0.4, 1.0),
+            )?;
+            graphics::draw(ctx, &ring, (center(best),))?;
+            let text = graphics::Text::new(("Best", *font, REVIEW_FONT_SIZE));
+            let (w, h) = (text.width(ctx), text.height(ctx));
+            graphics::draw(
+                ctx,
+                &text,
+                (
+                    center(best) - Vec2::new(w / 2.0, h / 2.0),
+                    graphics::Color::new(0.2, 1.0, 0.4, 1.0),
+                ),
+            )?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
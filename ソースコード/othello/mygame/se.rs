@@ -1,7 +1,8 @@
 use ggez::audio::{SoundSource, Source};
 pub struct Se {
-    stone: Source,  //石を置いた時のSE
-    button: Source, //ボタンを押した時のSE
+    stone: Source,   //石を置いた時のSE
+    button: Source,  //ボタンを押した時のSE
+    illegal: Source, //置けないマスをクリックした時のSE
 }
 impl Se {
     pub fn new(ctx: &mut ggez::Context) -> Se {
@@ -9,9 +10,13 @@ impl Se {
         let mut button = Source::new(ctx, "/button.mp3").unwrap();
         button.set_volume(0.5);//少々うるさいのでボリュームを下げる
         stone.set_volume(0.5);//少々うるさいのでボリュームを下げる
+        let mut illegal = Source::new(ctx, "/button.mp3").unwrap(); //ボタンのSEを低くして使う
+        illegal.set_volume(0.4);
+        illegal.set_pitch(0.5);
         Se {
             stone: stone,
             button: button,
+            illegal: illegal,
         }
     }
     pub fn play_stone(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
@@ -22,4 +27,8 @@ impl Se {
         self.button.play_detached(ctx).unwrap();
         Ok(())
     }
+    pub fn play_illegal(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult<()> {
+        self.illegal.play_detached(ctx).unwrap();
+        Ok(())
+    }
 }
@@ -0,0 +1,78 @@
+use crate::mygame::difficulty::Difficulty;
+use std::io::Write;
+
+/*
+    次回の起動時にも引き継ぐ設定
+    SETTINGS_FILEに"キー=値"の形式で1行ずつ保存する
+    ファイルがない場合や読めない項目は既定値を使う
+*/
+pub const SETTINGS_FILE: &str = "./settings.txt";
+
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    pub illegal_sound: bool, //置けないマスをクリックしたときに音を鳴らすか
+    pub coach: bool,         //コーチモード(悪い手を打つ前に警告する)
+    pub explain: bool,       //CPUの手の説明を表示する
+}
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            difficulty: Difficulty::default(),
+            illegal_sound: true,
+            coach: false,
+            explain: false,
+        }
+    }
+}
+impl Settings {
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+        let text = match std::fs::read_to_string(SETTINGS_FILE) {
+            Ok(text) => text,
+            Err(_) => return settings,
+        };
+        for line in text.lines() {
+            let mut kv = line.splitn(2, '=');
+            let (key, value) = (kv.next().unwrap().trim(), kv.next().unwrap_or("").trim());
+            match key {
+                "difficulty" => match Difficulty::from_name(value) {
+                    Some(d) => settings.difficulty = d,
+                    None => eprintln!("設定の難易度が不正です: {}", value),
+                },
+                "illegal_sound" => match value {
+                    "on" => settings.illegal_sound = true,
+                    "off" => settings.illegal_sound = false,
+                    _ => eprintln!("設定のillegal_soundはonかoffです: {}", value),
+                },
+                "coach" => match value {
+                    "on" => settings.coach = true,
+                    "off" => settings.coach = false,
+                    _ => eprintln!("設定のcoachはonかoffです: {}", value),
+                },
+                "explain" => match value {
+                    "on" => settings.explain = true,
+                    "off" => settings.explain = false,
+                    _ => eprintln!("設定のexplainはonかoffです: {}", value),
+                },
+                _ => {}
+            }
+        }
+        settings
+    }
+    pub fn save(&self) {
+        let result = std::fs::File::create(SETTINGS_FILE).and_then(|mut file| {
+            writeln!(file, "difficulty={}", self.difficulty.name())?;
+            writeln!(
+                file,
+                "illegal_sound={}",
+                if self.illegal_sound { "on" } else { "off" }
+            )?;
+            writeln!(file, "coach={}", if self.coach { "on" } else { "off" })?;
+            writeln!(file, "explain={}", if self.explain { "on" } else { "off" })
+        });
+        if let Err(e) = result {
+            eprintln!("設定を{}へ保存できません: {}", SETTINGS_FILE, e);
+        }
+    }
+}
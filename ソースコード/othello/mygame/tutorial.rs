@@ -0,0 +1,161 @@
+use crate::mygame::{predict, Board, Stone};
+use ggez::{graphics, Context, GameResult};
+
+/*
+    ルールのチュートリアル
+    用意した局面で，石の置き方・8方向にひっくり返すこと・パス・勝ち負けの数え方を順に説明する
+    各段階で指示した操作(石を置く・パスする)をするまで次の段階へ進めない
+*/
+enum Task {
+    AnyMove,              //置ける場所のどこかに置く
+    Move((usize, usize)), //指定したマスに置く
+    Pass,                 //パスボタンを押す
+    Nothing,              //説明のみ
+}
+struct Step {
+    board: &'static str, //Board::to_textの形式
+    color: Stone,        //手番(プレイヤーの白)
+    text: &'static str,
+    task: Task,
+    done: &'static str, //指示した操作をした後の説明
+}
+const STEPS: [Step; 5] = [
+    Step {
+        board: "---------------------------OX------XO---------------------------",
+        color: Stone::White,
+        text: "1/5 石の置き方\nあなたは白です．黒の石を白の石で挟める場所に\nだけ置けます．印のついたマスに置いてください",
+        task: Task::AnyMove,
+        done: "挟んだ黒の石が白にひっくり返りました",
+    },
+    Step {
+        board: "---------O-O-O----XXX----OX-XO----XXX----O-O-O------------------",
+        color: Stone::White,
+        text: "2/5 8方向にひっくり返す\n挟める方向は縦・横・斜めの8方向です．d4に置くと\n光っている8つの石が一度に返ります",
+        task: Task::Move((3, 3)),
+        done: "8方向の石がすべて白になりました",
+    },
+    Step {
+        board: "-OXXXXXX--------------------------------------------------------",
+        color: Stone::White,
+        text: "3/5 パス\n白はどこにも置けません．置ける場所がないときは\npassボタンを押して黒に手番を渡します",
+        task: Task::Pass,
+        done: "どちらも置けなくなったら対局は終わりです",
+    },
+    Step {
+        board: "OOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOXXXXXXXXXXXXXXXXXXXXXXXXOOOOOOX-",
+        color: Stone::White,
+        text: "4/5 勝ち負けの数え方\n最後の空きマスh8に置いて対局を終わらせましょう",
+        task: Task::Move((7, 7)),
+        done: "終局したら石の多い方の勝ちです\n石の数は画面の下に表示されています",
+    },
+    Step {
+        board: "---------------------------OX------XO---------------------------",
+        color: Stone::White,
+        text: "5/5 おしまい\nルールの説明は以上です．\nExitボタンで対局に戻ります",
+        task: Task::Nothing,
+        done: "",
+    },
+];
+
+pub struct Tutorial {
+    pub index: usize,
+    pub done: bool,          //指示した操作をしたか
+    message: Option<String>, //指示と違う操作をしたときの説明
+}
+impl Tutorial {
+    pub fn new() -> Tutorial {
+        Tutorial {
+            index: 0,
+            done: false,
+            message: None,
+        }
+    }
+    fn step(&self) -> &'static Step {
+        &STEPS[self.index]
+    }
+    //現在の段階の局面と手番
+    pub fn position(&self, width: u32, height: u32) -> Option<(Board, Stone)> {
+        let step = self.step();
+        Some((Board::from_text(step.board, width, height)?, step.color))
+    }
+    //置く場所を指定している場合，そのマス(置いた後はNone)
+    pub fn target(&self) -> Option<(usize, usize)> {
+        match self.step().task {
+            Task::Move(pos) if !self.done => Some(pos),
+            _ => None,
+        }
+    }
+    /*
+        posに置こうとしたときに呼ぶ．指示通りであればtrueを返し(石は呼び出し側で置く)，
+        指示と違う場合はその理由を表示する
+    */
+    pub fn try_move(&mut self, board: &Board, pos: (usize, usize)) -> bool {
+        if self.done {
+            return false;
+        }
+        let step = self.step();
+        let legal = predict::can_cnt(&board.gamebord, step.color, pos, false).len() != 0;
+        let message = match step.task {
+            Task::AnyMove | Task::Move(_) if !legal => "そこには置けません",
+            Task::Move(target) if target != pos => "指定したマスに置いてください",
+            Task::Pass => "置ける場所がありません．passボタンを押してください",
+            Task::Nothing => return false,
+            _ => {
+                self.done = true;
+                self.message = None;
+                return true;
+            }
+        };
+        self.message = Some(message.to_string());
+        false
+    }
+    //パスボタンが押されたときに呼ぶ．パスする段階であればtrue
+    pub fn try_pass(&mut self) -> bool {
+        if self.done {
+            return false;
+        }
+        match self.step().task {
+            Task::Pass => {
+                self.done = true;
+                self.message = None;
+                true
+            }
+            Task::Nothing => false,
+            _ => {
+                self.message = Some("置ける場所があるときはパスできません".to_string());
+                false
+            }
+        }
+    }
+    /*
+        deltaだけ前後の段階へ移る．次へ進むのは指示した操作をした後のみ
+        移った場合はtrue
+    */
+    pub fn select(&mut self, delta: i32) -> bool {
+        let index = self.index as i32 + delta;
+        if index < 0 || index >= STEPS.len() as i32 || (delta > 0 && !self.done) {
+            return false;
+        }
+        self.index = index as usize;
+        self.done = match self.step().task {
+            Task::Nothing => true,
+            _ => false,
+        };
+        self.message = None;
+        true
+    }
+    //説明と指示を盤面の上側に描画する
+    pub fn draw(&self, ctx: &mut Context, font: &graphics::Font) -> GameResult<()> {
+        let step = self.step();
+        let mut text = step.text.to_string();
+        let mut color = graphics::Color::WHITE;
+        if let Some(message) = &self.message {
+            text += &format!("\n{}", message);
+            color = graphics::Color::new(1.0, 0.5, 0.5, 1.0);
+        } else if self.done && step.done.len() != 0 {
+            text += &format!("\n{}  >で次へ", step.done);
+            color = graphics::Color::new(0.5, 1.0, 0.5, 1.0);
+        }
+        super::draw_banner(ctx, font, &text, color)
+    }
+}
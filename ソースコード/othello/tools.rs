@@ -0,0 +1,9 @@
+/*
+    ウィンドウを開かずにコマンドラインで使う開発用ツール群
+*/
+pub mod book;
+pub mod eval;
+pub mod matchup;
+pub mod perft;
+pub mod replay;
+pub mod train;
@@ -0,0 +1,71 @@
+use crate::mygame::predict::book::{self, Book};
+use crate::mygame::record;
+
+/*
+    棋譜から定石ファイルを作成する
+    othello book <棋譜ファイル..> [--out ファイル] [--depth N] [--min N]
+        各棋譜の序盤depth手までを登録し，min回未満しか現れなかった手は除く
+*/
+const DEFAULT_DEPTH: usize = 12;
+const DEFAULT_MIN_WEIGHT: u32 = 2;
+
+pub fn run(args: &[String]) {
+    let mut files = Vec::new();
+    let mut out = book::BOOK_FILE.to_string();
+    let mut depth = DEFAULT_DEPTH;
+    let mut min_weight = DEFAULT_MIN_WEIGHT;
+    let mut i = 0;
+    while i < args.len() {
+        match (args[i].as_str(), args.get(i + 1)) {
+            ("--out", Some(path)) => {
+                out = path.clone();
+                i += 1;
+            }
+            ("--depth", Some(n)) | ("--min", Some(n)) => {
+                let ok = match args[i].as_str() {
+                    "--depth" => n.parse().map(|n| depth = n).is_ok(),
+                    _ => n.parse().map(|n| min_weight = n).is_ok(),
+                };
+                if !ok {
+                    eprintln!("{}の値が不正です: {}", args[i], n);
+                    return;
+                }
+                i += 1;
+            }
+            (path, _) => files.push(path.to_string()),
+        }
+        i += 1;
+    }
+    if files.len() == 0 {
+        eprintln!("使い方: othello book <棋譜ファイル..> [--out ファイル] [--depth N] [--min N]");
+        return;
+    }
+    let mut book = Book::new();
+    let (mut used, mut skipped) = (0, 0);
+    for path in files.iter() {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}を開けません: {}", path, e);
+                return;
+            }
+        };
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            match record::parse_transcript(line) {
+                Some(moves) if book::add_game(&mut book, &moves, depth) => used += 1,
+                _ => skipped += 1,
+            }
+        }
+    }
+    book.prune(min_weight);
+    println!(
+        "{}局から{}局面を登録しました (不正な棋譜{}局を除外)",
+        used,
+        book.len(),
+        skipped
+    );
+    match std::fs::write(&out, book.to_text()) {
+        Ok(()) => println!("{}へ保存しました", out),
+        Err(e) => eprintln!("{}へ保存できません: {}", out, e),
+    }
+}
@@ -0,0 +1,98 @@
+use crate::mygame::board::Board;
+use crate::mygame::predict::evaluate::{evaluate, EvalWeights, Features};
+use crate::mygame::predict::transposition::{self, TranspositionTable};
+use crate::mygame::predict::{endgame, pattern};
+use crate::mygame::stone::Stone;
+use crate::mygame::BOARDSIZE;
+
+/*
+    評価関数の各特徴量と評価値を表示する(重みの調整用)
+    othello eval <盤面64文字> <X|O> [重み]
+    重みはEvalWeights::parseの形式(例: mobility=10,stable=20)
+    空きマスがendgame::SOLVE_EMPTIES以下なら完全読みの結果(候補手ごとの石数差と読み筋)も表示する
+*/
+pub fn run(args: &[String]) {
+    if args.len() < 2 {
+        eprintln!("使い方: othello eval <盤面64文字> <X|O> [重み]");
+        return;
+    }
+    let board = match Board::from_text(&args[0], 0, 0) {
+        Some(board) => board,
+        None => {
+            eprintln!("盤面を解釈できません: {}", args[0]);
+            return;
+        }
+    };
+    let color = if args[1] == "X" {
+        Stone::Black
+    } else {
+        Stone::White
+    };
+    let weights = match EvalWeights::parse(args.get(2).map(|s| s.as_str()).unwrap_or("")) {
+        Ok(weights) => weights,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let f = Features::new(&board, color);
+    let rows = [
+        ("square", f.square, weights.square),
+        ("mobility", f.mobility, weights.mobility),
+        (
+            "potential_mobility",
+            f.potential_mobility,
+            weights.potential_mobility,
+        ),
+        ("frontier", f.frontier, weights.frontier),
+        ("corner", f.corner, weights.corner),
+        ("x_square", f.x_square, weights.x_square),
+        ("c_square", f.c_square, weights.c_square),
+        ("stable", f.stable, weights.stable),
+    ];
+    println!("{:?}番から見た評価", color);
+    for (name, value, weight) in rows.iter() {
+        println!(
+            "{:<20}{:>8.1} x {:>6.1} = {:>8.1}",
+            name,
+            value,
+            weight,
+            value * weight
+        );
+    }
+    println!(
+        "{:<20}{:>28.1}",
+        "評価値",
+        evaluate(&board, color, &weights)
+    );
+    if pattern::shared().is_some() {
+        println!(
+            "{:<20}{:>28.1}",
+            "パターン評価",
+            pattern::evaluate(&board, color)
+        );
+    }
+    let empties = BOARDSIZE * BOARDSIZE - (board.black_num + board.white_num) as usize;
+    if empties <= endgame::SOLVE_EMPTIES {
+        let mut table = TranspositionTable::new(transposition::TABLE_BITS);
+        let stats = endgame::search(&board, color, &mut table);
+        println!(
+            "{:<20}{:>28} (最善手 {})",
+            "完全読み",
+            stats.score.unwrap_or(0),
+            stats
+                .best
+                .map(Board::pos_to_notation)
+                .unwrap_or("パス".to_string())
+        );
+        for child in stats.children.iter() {
+            println!(
+                "  {} {:>+4} ({}局面)",
+                Board::pos_to_notation(child.pos),
+                child.score.unwrap_or(0),
+                child.visits
+            );
+        }
+        println!("{}", stats);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::mygame::predict::stats::{SearchMethod, SearchStats};
+use crate::mygame::predict::{self, external::ExternalEngine, EngineConfig};
+use crate::mygame::random::{self, GameRng};
+use crate::mygame::{board::Board, record, stone::Stone};
+use std::time::{Duration, Instant};
+
+/*
+    2つのエンジンを色を入れ替えながら対戦させ，結果の統計を表示する
+    othello match <エンジンA> <エンジンB> [--games N] [--openings ファイル] [--seed N]
+    エンジンの指定
+        mcts[:maxtry=4000,c=3.14] : 組み込みのモンテカルロ木探索
+        ext:<コマンド> [引数..]    : 外部エンジン(引数ごと一つの文字列で渡す)
+    openingsのファイルには1行に1つ棋譜を書く．各序盤から先後を入れ替えて2局ずつ打つ
+    各局のシードはrandom::derive(seed,局数)，一手ごとの乱数はさらにその手数から作る
+*/
+const DEFAULT_GAMES: usize = 10;
+
+enum Contestant {
+    Builtin(EngineConfig),
+    External(ExternalEngine),
+}
+impl Contestant {
+    fn from_spec(spec: &str) -> Result<Contestant, String> {
+        if spec == "mcts" {
+            return Ok(Contestant::Builtin(EngineConfig::default()));
+        }
+        if spec.starts_with("mcts:") {
+            return Ok(Contestant::Builtin(EngineConfig::parse(&spec[5..])?));
+        }
+        if spec.starts_with("ext:") {
+            let words: Vec<String> = spec[4..]
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect();
+            if words.len() == 0 {
+                return Err("外部エンジンのコマンドがありません".to_string());
+            }
+            return ExternalEngine::spawn(&words[0], &words[1..])
+                .map(Contestant::External)
+                .map_err(|e| e.to_string());
+        }
+        Err(format!("エンジンの指定が不正です: {}", spec))
+    }
+    /*
+        置く位置を決め，その思考の統計を返す
+        info行を出力しない外部エンジンの統計は着手のみとなる
+    */
+    fn choose(
+        &mut self,
+        board: &Board,
+        color: Stone,
+        random: &mut GameRng,
+    ) -> Result<SearchStats, String> {
+        match self {
+            Contestant::Builtin(config) => Ok(predict::search(board, color, config, random)),
+            Contestant::External(engine) => {
+                let pos = engine
+                    .request_move(board, color)
+                    .map_err(|e| e.to_string())?;
+                let mut stats = engine
+                    .last_stats()
+                    .unwrap_or_else(|| SearchStats::new(SearchMethod::External));
+                stats.best = pos;
+                Ok(stats)
+            }
+        }
+    }
+}
+
+//一手ごとの思考時間と探索の統計の集計
+#[derive(Default)]
+struct Timing {
+    total: Duration,
+    max: Duration,
+    moves: u32,
+    nodes: usize,     //SearchStats::nodesの合計
+    max_depth: usize, //SearchStats::max_depthの最大
+}
+impl Timing {
+    fn add(&mut self, elapsed: Duration, stats: &SearchStats) {
+        self.total += elapsed;
+        self.max = std::cmp::max(self.max, elapsed);
+        self.moves += 1;
+        self.nodes += stats.nodes;
+        self.max_depth = std::cmp::max(self.max_depth, stats.max_depth);
+    }
+    fn nps(&self) -> f64 {
+        if self.total.as_secs_f64() <= 0.0 {
+            return 0.0;
+        }
+        self.nodes as f64 / self.total.as_secs_f64()
+    }
+    fn average_ms(&self) -> f64 {
+        if self.moves == 0 {
+            return 0.0;
+        }
+        self.total.as_secs_f64() * 1000.0 / self.moves as f64
+    }
+}
+
+/*
+    一局打ち，Aから見た石数の差を返す
+    a_color:エンジンAの色
+*/
+fn play_game(
+    players: &mut [Contestant; 2],
+    timings: &mut [Timing; 2],
+    opening: &[(usize, usize)],
+    a_color: Stone,
+    seed: u64,
+) -> Result<i32, String> {
+    let (mut board, mut color) = record::replay(opening, 0, 0)
+        .ok_or_else(|| format!("序盤の棋譜が不正です: {}", record::to_transcript(opening)))?;
+    loop {
+        if predict::can_set_pos(&board, color).len() == 0 {
+            if predict::can_set_pos(&board, color.return_reverse_color()).len() == 0 {
+                break; //両者置けないので終局
+            }
+            color.reversestone(); //パス
+            continue;
+        }
+        let index = if color as i32 == a_color as i32 { 0 } else { 1 };
+        let moves = (board.black_num + board.white_num - 4) as u64;
+        let mut random = random::from_seed(random::derive(seed, moves));
+        let start = Instant::now();
+        let stats = players[index].choose(&board, color, &mut random)?;
+        timings[index].add(start.elapsed(), &stats);
+        match stats.best {
+            Some(pos) if predict::put_stone(&mut board, color, pos) => {}
+            _ => return Err("エンジンが置けない手を返しました".to_string()),
+        }
+        color.reversestone();
+    }
+    let (a_num, b_num) = match a_color {
+        Stone::Black => (board.black_num, board.white_num),
+        _ => (board.white_num, board.black_num),
+    };
+    Ok(a_num as i32 - b_num as i32)
+}
+
+/*
+    勝率からElo差を求める
+*/
+fn elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        return std::f64::NEG_INFINITY;
+    }
+    if score >= 1.0 {
+        return std::f64::INFINITY;
+    }
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+pub fn run(args: &[String]) {
+    if args.len() < 2 {
+        eprintln!(
+            "使い方: othello match <エンジンA> <エンジンB> [--games N] [--openings ファイル] [--seed N]"
+        );
+        return;
+    }
+    let mut games = DEFAULT_GAMES;
+    let mut openings: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut seed = random::new_seed();
+    let mut i = 2;
+    while i < args.len() {
+        match (args[i].as_str(), args.get(i + 1)) {
+            ("--games", Some(n)) => match n.parse() {
+                Ok(n) => games = n,
+                Err(_) => {
+                    eprintln!("対局数が不正です: {}", n);
+                    return;
+                }
+            },
+            ("--seed", Some(n)) => match n.parse() {
+                Ok(n) => seed = n,
+                Err(_) => {
+                    eprintln!("シードが不正です: {}", n);
+                    return;
+                }
+            },
+            ("--openings", Some(path)) => match std::fs::read_to_string(path) {
+                Ok(text) => {
+                    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                        match record::parse_transcript(line) {
+                            Some(moves) => openings.push(moves),
+                            None => eprintln!("棋譜を読み込めません: {}", line),
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}を開けません: {}", path, e);
+                    return;
+                }
+            },
+            _ => {
+                eprintln!("不明な引数です: {}", args[i]);
+                return;
+            }
+        }
+        i += 2;
+    }
+    if openings.len() == 0 {
+        openings.push(Vec::new());
+    }
+    let mut players = match (
+        Contestant::from_spec(&args[0]),
+        Contestant::from_spec(&args[1]),
+    ) {
+        (Ok(a), Ok(b)) => [a, b],
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    println!("シード: {}", seed);
+    let mut timings = [Timing::default(), Timing::default()];
+    let mut scores: Vec<f64> = Vec::with_capacity(games);
+    let (mut win, mut lose, mut draw, mut diff_sum) = (0, 0, 0, 0);
+    for game in 0..games {
+        let opening = &openings[(game / 2) % openings.len()];
+        //偶数局目はAが先手，奇数局目はBが先手
+        let first = match record::replay(opening, 0, 0) {
+            Some((_, color)) => color,
+            None => {
+                eprintln!("序盤の棋譜が不正です: {}", record::to_transcript(opening));
+                return;
+            }
+        };
+        let a_color = if game % 2 == 0 {
+            first
+        } else {
+            first.return_reverse_color()
+        };
+        let game_seed = random::derive(seed, game as u64);
+        let diff = match play_game(&mut players, &mut timings, opening, a_color, game_seed) {
+            Ok(diff) => diff,
+            Err(e) => {
+                eprintln!("{}局目を中断しました: {}", game + 1, e);
+                return;
+            }
+        };
+        diff_sum += diff;
+        if diff > 0 {
+            win += 1;
+            scores.push(1.0);
+        } else if diff < 0 {
+            lose += 1;
+            scores.push(0.0);
+        } else {
+            draw += 1;
+            scores.push(0.5);
+        }
+        println!(
+            "{}局目: Aが{:?} 石数差{:+} (A {}勝 {}敗 {}分)",
+            game + 1,
+            a_color,
+            diff,
+            win,
+            lose,
+            draw
+        );
+    }
+    if scores.len() == 0 {
+        return;
+    }
+    let n = scores.len() as f64;
+    let mean = scores.iter().sum::<f64>() / n;
+    let variance = scores.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / n;
+    let margin = 1.96 * (variance / n).sqrt(); //95%信頼区間
+    println!();
+    println!("A: {}  B: {}", args[0], args[1]);
+    println!(
+        "Aの成績: {}勝 {}敗 {}分 (勝率{:.1}%)",
+        win,
+        lose,
+        draw,
+        mean * 100.0
+    );
+    println!("平均石数差: {:+.2}", diff_sum as f64 / n);
+    println!(
+        "Elo差: {:+.1} (95%信頼区間 {:+.1} 〜 {:+.1})",
+        elo(mean),
+        elo(mean - margin),
+        elo(mean + margin)
+    );
+    for (name, timing) in ["A", "B"].iter().zip(timings.iter()) {
+        println!(
+            "{}の思考時間: 平均{:.1}ms 最大{:.1}ms ({}手) {:.0}局面/秒 最大深さ{}",
+            name,
+            timing.average_ms(),
+            timing.max.as_secs_f64() * 1000.0,
+            timing.moves,
+            timing.nps(),
+            timing.max_depth
+        );
+    }
+}
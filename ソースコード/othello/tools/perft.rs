@@ -0,0 +1,128 @@
+use crate::mygame::board::Board;
+use crate::mygame::predict;
+use crate::mygame::stone::Stone;
+use crate::mygame::BOARDSIZE;
+use std::time::Instant;
+
+/*
+    合法手生成(can_set_pos, can_cnt)の検証用に，指定した深さまでの末端局面数を数える
+    othello perft <深さ> [盤面64文字 X|O]
+    パスも1手として深さを消費する．両者置けない終局局面は，その時点で末端として1と数える
+    初期局面から数えた場合は，公表されている値と比較する
+*/
+//初期局面からの末端局面数(深さ1から)
+const KNOWN_PERFT: [u64; 14] = [
+    4,
+    12,
+    56,
+    244,
+    1396,
+    8200,
+    55092,
+    390216,
+    3005288,
+    24571284,
+    212258800,
+    1939886636,
+    18429641748,
+    184042084512,
+];
+
+pub fn perft(board: &Board, color: Stone, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let list = predict::can_set_pos(board, color);
+    if list.len() == 0 {
+        if predict::can_set_pos(board, color.return_reverse_color()).len() == 0 {
+            return 1; //終局
+        }
+        return perft(board, color.return_reverse_color(), depth - 1); //パス
+    }
+    if depth == 1 {
+        return list.len() as u64;
+    }
+    let mut ret = 0;
+    for pos in list {
+        let mut next = *board;
+        next.setstone(&predict::can_cnt(&board.gamebord, color, pos, false), color);
+        ret += perft(&next, color.return_reverse_color(), depth - 1);
+    }
+    ret
+}
+
+pub fn run(args: &[String]) {
+    let depth: u32 = match args.get(0).map(|s| s.parse()) {
+        Some(Ok(depth)) if depth > 0 => depth,
+        _ => {
+            eprintln!("使い方: othello perft <深さ> [盤面64文字 X|O]");
+            return;
+        }
+    };
+    let (board, color, from_start) = match (args.get(1), args.get(2)) {
+        (Some(text), Some(side)) => match Board::from_text(text, 0, 0) {
+            Some(board) => {
+                let color = if side == "X" {
+                    Stone::Black
+                } else {
+                    Stone::White
+                };
+                (board, color, false)
+            }
+            None => {
+                eprintln!("盤面を解釈できません: {}", text);
+                return;
+            }
+        },
+        _ => (Board::new(BOARDSIZE, 0, 0), Stone::White, true),
+    };
+    let start = Instant::now();
+    //初手ごとの内訳(divide)を表示
+    let mut total = 0;
+    let list = predict::can_set_pos(&board, color);
+    if list.len() == 0 {
+        total = perft(&board, color, depth);
+        println!("pass: {}", total);
+    }
+    for pos in list {
+        let mut next = board;
+        next.setstone(&predict::can_cnt(&board.gamebord, color, pos, false), color);
+        let count = perft(&next, color.return_reverse_color(), depth - 1);
+        println!("{}: {}", Board::pos_to_notation(pos), count);
+        total += count;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!();
+    println!("深さ{}: {}", depth, total);
+    println!(
+        "{:.3}秒 ({:.0}局面/秒)",
+        elapsed,
+        total as f64 / elapsed.max(1e-9)
+    );
+    if from_start && depth as usize <= KNOWN_PERFT.len() {
+        let known = KNOWN_PERFT[depth as usize - 1];
+        if known == total {
+            println!("公表値と一致しました");
+        } else {
+            println!("公表値({})と一致しません", known);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_position_matches_known_counts() {
+        let board = Board::new(BOARDSIZE, 0, 0);
+        for depth in 1..=8 {
+            assert_eq!(
+                perft(&board, Stone::White, depth),
+                KNOWN_PERFT[depth as usize - 1],
+                "深さ{}",
+                depth
+            );
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::mygame::predict::{self, EngineConfig};
+use crate::mygame::{board::Board, random, record, CPU_COLOR};
+
+/*
+    対局の記録(record::GAME_RECORD_FILEの1行)から，本線のCPUの手を同じシードで思考し直して一致するかを確かめる
+    othello replay "<対局の記録>" [エンジン設定]
+    エンジン設定を省略した場合は記録にあるもの(なければ既定の設定)を使う
+    定石・パターン評価のファイルも対局時と同じである必要がある
+*/
+pub fn run(args: &[String]) {
+    if args.len() < 1 {
+        eprintln!("使い方: othello replay \"<対局の記録>\" [エンジン設定]");
+        return;
+    }
+    let game = match record::GameRecord::parse(&args[0]) {
+        Some(game) => game,
+        None => {
+            eprintln!("対局の記録を解釈できません: {}", args[0]);
+            return;
+        }
+    };
+    let (seed, moves) = (game.seed, game.moves());
+    let spec = args.get(1).or(game.config.as_ref()).map(|s| s.as_str());
+    let config = match EngineConfig::parse(spec.unwrap_or("")) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    println!("シード: {}", seed);
+    let (mut mismatch, mut checked) = (0, 0);
+    for i in 0..moves.len() {
+        let (board, color) = match game.tree.replay(&moves[..i], 0, 0) {
+            Some(v) => v,
+            None => {
+                eprintln!("{}手目が置けない手です", i);
+                return;
+            }
+        };
+        if color as i32 != CPU_COLOR as i32 {
+            continue;
+        }
+        //GUIと同じく，一手ごとの乱数はシードと手数から作る
+        let mut random = random::from_seed(random::derive(seed, i as u64));
+        let pos = predict::best_move(&board, color, &config, &mut random);
+        checked += 1;
+        if pos != Some(moves[i]) {
+            mismatch += 1;
+            println!(
+                "{}手目: 記録 {} 再現 {}",
+                i + 1,
+                Board::pos_to_notation(moves[i]),
+                pos.map(Board::pos_to_notation)
+                    .unwrap_or("パス".to_string())
+            );
+        }
+    }
+    println!(
+        "CPUの手{}手中{}手が一致しました",
+        checked,
+        checked - mismatch
+    );
+}
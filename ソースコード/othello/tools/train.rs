@@ -0,0 +1,205 @@
+use crate::mygame::predict::{self, pattern::PatternEval, EngineConfig};
+use crate::mygame::{random, record, stone::Stone};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/*
+    パターン評価の重みの学習
+    othello selfplay <対局数> <出力ファイル> [エンジン設定]
+        モンテカルロ木探索同士で対局し，棋譜を1行ずつ追記する
+        序盤のRANDOM_OPENING手はランダムに打って局面を散らす
+        乱数のシードは開始時に表示し，各局のシードはrandom::derive(シード,局数)とする
+    othello train <棋譜ファイル..> [--out ファイル] [--epochs N]
+        終局まで打たれた棋譜の各局面に最終的な石数差を付け，パターン評価の重みを回帰で求める
+*/
+const RANDOM_OPENING: usize = 8;
+const SELFPLAY_MAXTRY: usize = 1000;
+const DEFAULT_EPOCHS: usize = 20;
+const LEARNING_RATE: f32 = 0.05;
+
+pub fn run_selfplay(args: &[String]) {
+    if args.len() < 2 {
+        eprintln!("使い方: othello selfplay <対局数> <出力ファイル> [エンジン設定]");
+        return;
+    }
+    let games: usize = match args[0].parse() {
+        Ok(n) => n,
+        Err(_) => {
+            eprintln!("対局数が不正です: {}", args[0]);
+            return;
+        }
+    };
+    let mut config = EngineConfig::default();
+    config.maxtry = SELFPLAY_MAXTRY;
+    if let Some(spec) = args.get(2) {
+        config = match EngineConfig::parse(spec) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+    }
+    let mut out = match OpenOptions::new().create(true).append(true).open(&args[1]) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("{}を開けません: {}", args[1], e);
+            return;
+        }
+    };
+    let seed = random::new_seed();
+    println!("シード: {}", seed);
+    for game in 0..games {
+        let mut random = random::from_seed(random::derive(seed, game as u64));
+        let (mut board, mut color) = record::replay(&[], 0, 0).unwrap();
+        let mut moves = Vec::new();
+        loop {
+            let list = predict::can_set_pos(&board, color);
+            if list.len() == 0 {
+                if predict::can_set_pos(&board, color.return_reverse_color()).len() == 0 {
+                    break;
+                }
+                color.reversestone();
+                continue;
+            }
+            let pos = if moves.len() < RANDOM_OPENING {
+                list[random.gen_range(0, list.len())]
+            } else {
+                predict::montecarlo::search(&board, color, &config, &mut random)
+                    .best
+                    .unwrap()
+            };
+            predict::put_stone(&mut board, color, pos);
+            moves.push(pos);
+            color.reversestone();
+        }
+        if let Err(e) = writeln!(out, "{}", record::to_transcript(&moves)) {
+            eprintln!("書き込みに失敗しました: {}", e);
+            return;
+        }
+        println!(
+            "{}局目: 黒{} 白{}",
+            game + 1,
+            board.black_num,
+            board.white_num
+        );
+    }
+}
+
+//学習用の局面一つ分
+struct Sample {
+    phase: usize,
+    indices: Vec<usize>,
+    target: f32, //手番側から見た最終的な石数差
+}
+
+/*
+    棋譜を終局まで打ち進め，途中の各局面を学習用の局面として返す
+    終局していない棋譜や不正な棋譜はNone
+*/
+fn samples_from_game(eval: &PatternEval, moves: &[(usize, usize)]) -> Option<Vec<Sample>> {
+    let mut positions = Vec::with_capacity(moves.len());
+    for i in 0..moves.len() {
+        let (board, color) = record::replay(&moves[..i], 0, 0)?;
+        positions.push((board, color));
+    }
+    let (last, color) = record::replay(moves, 0, 0)?;
+    if predict::can_set_pos(&last, color).len() != 0 {
+        return None; //終局していない
+    }
+    let diff = last.white_num as f32 - last.black_num as f32;
+    Some(
+        positions
+            .iter()
+            .map(|(board, color)| Sample {
+                phase: PatternEval::phase(board),
+                indices: eval.indices(board, *color),
+                target: match color {
+                    Stone::White => diff,
+                    _ => -diff,
+                },
+            })
+            .collect(),
+    )
+}
+
+pub fn run_train(args: &[String]) {
+    let mut files = Vec::new();
+    let mut out = predict::pattern::PATTERN_FILE.to_string();
+    let mut epochs = DEFAULT_EPOCHS;
+    let mut i = 0;
+    while i < args.len() {
+        match (args[i].as_str(), args.get(i + 1)) {
+            ("--out", Some(path)) => {
+                out = path.clone();
+                i += 1;
+            }
+            ("--epochs", Some(n)) => {
+                match n.parse() {
+                    Ok(n) => epochs = n,
+                    Err(_) => {
+                        eprintln!("エポック数が不正です: {}", n);
+                        return;
+                    }
+                }
+                i += 1;
+            }
+            (path, _) => files.push(path.to_string()),
+        }
+        i += 1;
+    }
+    if files.len() == 0 {
+        eprintln!("使い方: othello train <棋譜ファイル..> [--out ファイル] [--epochs N]");
+        return;
+    }
+    let mut eval = PatternEval::new();
+    let mut samples = Vec::new();
+    let (mut used, mut skipped) = (0, 0);
+    for path in files.iter() {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}を開けません: {}", path, e);
+                return;
+            }
+        };
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            match record::parse_transcript(line).and_then(|m| samples_from_game(&eval, &m)) {
+                Some(mut list) => {
+                    samples.append(&mut list);
+                    used += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+    }
+    println!(
+        "{}局({}局面)を学習に使用します (終局していない等で{}局を除外)",
+        used,
+        samples.len(),
+        skipped
+    );
+    if samples.len() == 0 {
+        return;
+    }
+    let mut random = rand::thread_rng();
+    for epoch in 0..epochs {
+        samples.shuffle(&mut random);
+        let mut squared = 0.0;
+        for sample in samples.iter() {
+            let error = eval.train(sample.phase, &sample.indices, sample.target, LEARNING_RATE);
+            squared += error * error;
+        }
+        println!(
+            "エポック{}: 平均二乗誤差 {:.3}",
+            epoch + 1,
+            squared / samples.len() as f32
+        );
+    }
+    match eval.save(&out) {
+        Ok(()) => println!("{}へ保存しました", out),
+        Err(e) => eprintln!("{}へ保存できません: {}", out, e),
+    }
+}